@@ -0,0 +1,239 @@
+use crate::error::RembgError;
+
+/// Transfer function from mask probability to output alpha in non-binary mode
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum AlphaCurve {
+    /// Steep linear ramp starting at `threshold`
+    #[default]
+    Linear,
+    /// Ramp from `threshold` to 255 shaped by a gamma exponent (>1 = softer edges)
+    Gamma(f32),
+    /// Probabilities up to `low` are transparent, from `high` opaque,
+    /// with a gamma-shaped ramp in between. `threshold` is ignored.
+    Levels { low: u8, high: u8, gamma: f32 },
+    /// Hermite smoothstep between `low` and `high`. `threshold` is ignored.
+    Smoothstep { low: u8, high: u8 },
+    /// Custom lookup table of 256 alpha values indexed by probability
+    Lut(Vec<u8>),
+}
+
+impl AlphaCurve {
+    /// Build the 256-entry probability to alpha table for the given threshold
+    pub fn lut(&self, threshold: u8) -> Result<[u8; 256], RembgError> {
+        let mut lut = [0u8; 256];
+
+        match self {
+            AlphaCurve::Linear => {
+                let thr_f = threshold as f32;
+                for (i, entry) in lut.iter_mut().enumerate() {
+                    *entry = if threshold < 255 {
+                        let scale = 255.0 / (255.0 - thr_f);
+                        ((i as f32 - thr_f) * scale * 255.0)
+                            .clamp(0.0, 255.0)
+                            .round() as u8
+                    } else if i == 255 {
+                        255
+                    } else {
+                        0
+                    };
+                }
+            }
+            AlphaCurve::Gamma(gamma) => {
+                if threshold < 255 {
+                    fill_levels(&mut lut, threshold, 255, *gamma)?;
+                } else {
+                    // No room for a ramp, only certain pixels are kept
+                    lut[255] = 255;
+                }
+            }
+            AlphaCurve::Levels { low, high, gamma } => {
+                fill_levels(&mut lut, *low, *high, *gamma)?;
+            }
+            AlphaCurve::Smoothstep { low, high } => {
+                check_range(*low, *high)?;
+                let (lo, hi) = (*low as f32, *high as f32);
+                for (i, entry) in lut.iter_mut().enumerate() {
+                    let t = ((i as f32 - lo) / (hi - lo)).clamp(0.0, 1.0);
+                    let s = t * t * (3.0 - 2.0 * t);
+                    *entry = (s * 255.0).round() as u8;
+                }
+            }
+            AlphaCurve::Lut(values) => {
+                if values.len() != 256 {
+                    return Err(RembgError::InvalidInput(format!(
+                        "Alpha LUT must have 256 entries, got {}",
+                        values.len()
+                    )));
+                }
+                lut.copy_from_slice(values);
+            }
+        }
+
+        Ok(lut)
+    }
+}
+
+fn check_range(low: u8, high: u8) -> Result<(), RembgError> {
+    if low >= high {
+        return Err(RembgError::InvalidInput(format!(
+            "Alpha curve low level {} must be below high level {}",
+            low, high
+        )));
+    }
+    Ok(())
+}
+
+fn fill_levels(lut: &mut [u8; 256], low: u8, high: u8, gamma: f32) -> Result<(), RembgError> {
+    check_range(low, high)?;
+    if gamma <= 0.0 || gamma.is_nan() {
+        return Err(RembgError::InvalidInput(format!(
+            "Alpha curve gamma must be positive, got {}",
+            gamma
+        )));
+    }
+
+    let (lo, hi) = (low as f32, high as f32);
+    for (i, entry) in lut.iter_mut().enumerate() {
+        let t = ((i as f32 - lo) / (hi - lo)).clamp(0.0, 1.0);
+        *entry = (t.powf(gamma) * 255.0).round() as u8;
+    }
+    Ok(())
+}
+
+impl std::str::FromStr for AlphaCurve {
+    type Err = String;
+
+    /// Parse `linear`, `gamma:G`, `levels:LOW,HIGH[,GAMMA]` or `smoothstep:LOW,HIGH`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, params) = s.split_once(':').unwrap_or((s, ""));
+        let values: Vec<&str> = if params.is_empty() {
+            Vec::new()
+        } else {
+            params.split(',').map(str::trim).collect()
+        };
+
+        let level = |v: &str| {
+            v.parse::<u8>()
+                .map_err(|e| format!("invalid level '{}': {}", v, e))
+        };
+        let float = |v: &str| {
+            v.parse::<f32>()
+                .map_err(|e| format!("invalid gamma '{}': {}", v, e))
+        };
+
+        match (name, values.as_slice()) {
+            ("linear", []) => Ok(AlphaCurve::Linear),
+            ("gamma", [g]) => Ok(AlphaCurve::Gamma(float(g)?)),
+            ("levels", [lo, hi]) => Ok(AlphaCurve::Levels {
+                low: level(lo)?,
+                high: level(hi)?,
+                gamma: 1.0,
+            }),
+            ("levels", [lo, hi, g]) => Ok(AlphaCurve::Levels {
+                low: level(lo)?,
+                high: level(hi)?,
+                gamma: float(g)?,
+            }),
+            ("smoothstep", [lo, hi]) => Ok(AlphaCurve::Smoothstep {
+                low: level(lo)?,
+                high: level(hi)?,
+            }),
+            _ => Err(format!(
+                "invalid alpha curve '{}': expected linear, gamma:G, levels:LOW,HIGH[,GAMMA] or smoothstep:LOW,HIGH",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_is_a_step_at_the_threshold() {
+        let lut = AlphaCurve::Linear.lut(160).unwrap();
+        assert_eq!((lut[159], lut[160], lut[161]), (0, 0, 255));
+
+        let lut = AlphaCurve::Linear.lut(0).unwrap();
+        assert_eq!((lut[0], lut[1]), (0, 255));
+    }
+
+    #[test]
+    fn linear_at_255_keeps_only_certain_pixels() {
+        let lut = AlphaCurve::Linear.lut(255).unwrap();
+        assert!(lut[..255].iter().all(|&a| a == 0));
+        assert_eq!(lut[255], 255);
+    }
+
+    #[test]
+    fn gamma_at_255_keeps_only_certain_pixels() {
+        let lut = AlphaCurve::Gamma(2.0).lut(255).unwrap();
+        assert!(lut[..255].iter().all(|&a| a == 0));
+        assert_eq!(lut[255], 255);
+    }
+
+    #[test]
+    fn gamma_one_from_zero_is_identity() {
+        let lut = AlphaCurve::Gamma(1.0).lut(0).unwrap();
+        assert!(lut.iter().enumerate().all(|(i, &a)| a as usize == i));
+    }
+
+    #[test]
+    fn levels_and_smoothstep_ramp_between_low_and_high() {
+        let levels = AlphaCurve::Levels {
+            low: 50,
+            high: 100,
+            gamma: 1.0,
+        }
+        .lut(0)
+        .unwrap();
+        assert_eq!((levels[50], levels[75], levels[100]), (0, 128, 255));
+
+        let smooth = AlphaCurve::Smoothstep { low: 50, high: 100 }
+            .lut(0)
+            .unwrap();
+        assert_eq!((smooth[49], smooth[75], smooth[101]), (0, 128, 255));
+        assert!(smooth[55] < levels[55]);
+    }
+
+    #[test]
+    fn invalid_curves_are_rejected() {
+        let inverted = AlphaCurve::Levels {
+            low: 100,
+            high: 100,
+            gamma: 1.0,
+        };
+        assert!(inverted.lut(0).is_err());
+        assert!(AlphaCurve::Gamma(0.0).lut(128).is_err());
+        assert!(AlphaCurve::Gamma(f32::NAN).lut(128).is_err());
+        assert!(AlphaCurve::Lut(vec![0; 10]).lut(0).is_err());
+
+        let custom: Vec<u8> = (0..=255).rev().collect();
+        assert_eq!(AlphaCurve::Lut(custom).lut(0).unwrap()[0], 255);
+    }
+
+    #[test]
+    fn parses_curve_specs() {
+        assert_eq!("linear".parse::<AlphaCurve>(), Ok(AlphaCurve::Linear));
+        assert_eq!("gamma:2".parse::<AlphaCurve>(), Ok(AlphaCurve::Gamma(2.0)));
+        assert_eq!(
+            "levels:10, 200".parse::<AlphaCurve>(),
+            Ok(AlphaCurve::Levels {
+                low: 10,
+                high: 200,
+                gamma: 1.0
+            })
+        );
+        assert_eq!(
+            "smoothstep:20,220".parse::<AlphaCurve>(),
+            Ok(AlphaCurve::Smoothstep { low: 20, high: 220 })
+        );
+
+        assert!("gamma:x".parse::<AlphaCurve>().is_err());
+        assert!("levels:200".parse::<AlphaCurve>().is_err());
+        assert!("levels:10,300".parse::<AlphaCurve>().is_err());
+        assert!("linear:1".parse::<AlphaCurve>().is_err());
+        assert!("cubic".parse::<AlphaCurve>().is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use crate::inpaint::inpaint_transparent;
+use image::{ImageBuffer, Luma, Rgb, RgbImage, Rgba, RgbaImage};
+use imageproc::filter::gaussian_blur_f32;
+
+/// How a background image is scaled to the cutout size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ImageFit {
+    /// Fill the whole canvas, cropping the overflow
+    #[default]
+    Cover,
+    /// Fit inside the canvas, letterboxing with black
+    Contain,
+}
+
+/// Replacement background for the cutout
+#[derive(Debug, Clone)]
+pub enum Background {
+    /// Solid color
+    Color([u8; 3]),
+    /// Linear gradient; `angle` in degrees, 0 runs left to right, 90 top to bottom
+    LinearGradient {
+        from: [u8; 3],
+        to: [u8; 3],
+        angle: f32,
+    },
+    /// Radial gradient from the center to the corners
+    RadialGradient { inner: [u8; 3], outer: [u8; 3] },
+    /// Background picture
+    Image { image: RgbImage, fit: ImageFit },
+    /// Portrait ("bokeh") mode: the original background blurred with a Gaussian of
+    /// `radius` pixels, the subject edge feathered by `feather` pixels
+    Blur { radius: f32, feather: f32 },
+    /// Fill everything that is not fully opaque from the surrounding opaque pixels.
+    /// Together with `invert` this erases the subject from the scene.
+    Inpaint,
+}
+
+/// Composite the cutout onto the rendered background.
+///
+/// `scene` is the original image with the subject alpha, before any edge cleanup or
+/// sticker outline, so blurred and inpainted backgrounds come from the real scene.
+pub fn replace_background(
+    cutout: &RgbaImage,
+    scene: &RgbaImage,
+    background: &Background,
+) -> RgbImage {
+    let canvas = render_background(background, scene);
+    match background {
+        Background::Blur { feather, .. } if *feather > 0.0 => {
+            composite(&feather_alpha(cutout, *feather), &canvas)
+        }
+        _ => composite(cutout, &canvas),
+    }
+}
+
+/// Render the background at the size of `scene`, the original image with the subject alpha
+pub fn render_background(background: &Background, scene: &RgbaImage) -> RgbImage {
+    let (width, height) = scene.dimensions();
+    match background {
+        Background::Color(color) => RgbImage::from_pixel(width, height, Rgb(*color)),
+        Background::LinearGradient { from, to, angle } => {
+            let (sin, cos) = angle.to_radians().sin_cos();
+            // Project the corners on the gradient direction to normalize t to [0, 1]
+            let corners = [
+                (0.0, 0.0),
+                (width as f32, 0.0),
+                (0.0, height as f32),
+                (width as f32, height as f32),
+            ];
+            let projections = corners.map(|(x, y)| x * cos + y * sin);
+            let min = projections.iter().copied().fold(f32::INFINITY, f32::min);
+            let max = projections
+                .iter()
+                .copied()
+                .fold(f32::NEG_INFINITY, f32::max);
+            let span = (max - min).max(f32::EPSILON);
+
+            RgbImage::from_fn(width, height, |x, y| {
+                let p = (x as f32 + 0.5) * cos + (y as f32 + 0.5) * sin;
+                Rgb(mix(*from, *to, (p - min) / span))
+            })
+        }
+        Background::RadialGradient { inner, outer } => {
+            let cx = width as f32 / 2.0;
+            let cy = height as f32 / 2.0;
+            let radius = (cx * cx + cy * cy).sqrt().max(f32::EPSILON);
+
+            RgbImage::from_fn(width, height, |x, y| {
+                let dx = x as f32 + 0.5 - cx;
+                let dy = y as f32 + 0.5 - cy;
+                Rgb(mix(*inner, *outer, (dx * dx + dy * dy).sqrt() / radius))
+            })
+        }
+        Background::Image { image, fit } => fit_image(image, *fit, width, height),
+        Background::Blur { radius, .. } => blur_behind(scene, *radius),
+        Background::Inpaint => inpaint_transparent(scene),
+    }
+}
+
+/// Blur the transparent part of the cutout without bleeding subject colors into it.
+///
+/// Colors are weighted by transparency before blurring and normalized afterwards,
+/// pixels deep inside the subject fall back to a plain blur.
+fn blur_behind(cutout: &RgbaImage, radius: f32) -> RgbImage {
+    let (width, height) = cutout.dimensions();
+    if radius <= 0.0 {
+        return RgbImage::from_fn(width, height, |x, y| {
+            let p = cutout.get_pixel(x, y);
+            Rgb([p[0], p[1], p[2]])
+        });
+    }
+
+    let weighted: ImageBuffer<Rgba<f32>, Vec<f32>> = ImageBuffer::from_fn(width, height, |x, y| {
+        let p = cutout.get_pixel(x, y);
+        let w = 1.0 - p[3] as f32 / 255.0;
+        Rgba([p[0] as f32 * w, p[1] as f32 * w, p[2] as f32 * w, w])
+    });
+    let weighted = gaussian_blur_f32(&weighted, radius);
+
+    let plain = image::imageops::blur(cutout, radius);
+
+    RgbImage::from_fn(width, height, |x, y| {
+        let p = weighted.get_pixel(x, y);
+        let w = p[3];
+        if w > 0.01 {
+            Rgb([0, 1, 2].map(|c| (p[c] / w).round().clamp(0.0, 255.0) as u8))
+        } else {
+            let q = plain.get_pixel(x, y);
+            Rgb([q[0], q[1], q[2]])
+        }
+    })
+}
+
+/// Soften the alpha edge so the sharp subject blends into the blurred background
+fn feather_alpha(cutout: &RgbaImage, feather: f32) -> RgbaImage {
+    let (width, height) = cutout.dimensions();
+    let alpha: ImageBuffer<Luma<u8>, Vec<u8>> =
+        ImageBuffer::from_fn(width, height, |x, y| Luma([cutout.get_pixel(x, y)[3]]));
+    let alpha = gaussian_blur_f32(&alpha, feather);
+
+    let mut out = cutout.clone();
+    for (x, y, p) in out.enumerate_pixels_mut() {
+        p[3] = alpha.get_pixel(x, y)[0];
+    }
+    out
+}
+
+/// Put the cutout over an opaque background of the same size
+pub fn composite(foreground: &RgbaImage, background: &RgbImage) -> RgbImage {
+    let (width, height) = foreground.dimensions();
+    RgbImage::from_fn(width, height, |x, y| {
+        let fg = foreground.get_pixel(x, y);
+        let bg = background.get_pixel(x, y);
+        let a = fg[3] as f32 / 255.0;
+        Rgb([0, 1, 2].map(|c| (fg[c] as f32 * a + bg[c] as f32 * (1.0 - a)).round() as u8))
+    })
+}
+
+fn fit_image(image: &RgbImage, fit: ImageFit, width: u32, height: u32) -> RgbImage {
+    let (iw, ih) = image.dimensions();
+    if iw == 0 || ih == 0 {
+        return RgbImage::new(width, height);
+    }
+
+    let scale_x = width as f32 / iw as f32;
+    let scale_y = height as f32 / ih as f32;
+    let scale = match fit {
+        ImageFit::Cover => scale_x.max(scale_y),
+        ImageFit::Contain => scale_x.min(scale_y),
+    };
+    let sw = ((iw as f32 * scale).round() as u32).max(1);
+    let sh = ((ih as f32 * scale).round() as u32).max(1);
+    let scaled = image::imageops::resize(image, sw, sh, image::imageops::FilterType::Lanczos3);
+
+    // Center the scaled image on the canvas
+    let mut out = RgbImage::new(width, height);
+    let dx = (width as i64 - sw as i64) / 2;
+    let dy = (height as i64 - sh as i64) / 2;
+    image::imageops::replace(&mut out, &scaled, dx, dy);
+    out
+}
+
+fn mix(a: [u8; 3], b: [u8; 3], t: f32) -> [u8; 3] {
+    let t = t.clamp(0.0, 1.0);
+    [0, 1, 2].map(|c| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * t).round() as u8)
+}
+
+/// Parse a `#RRGGBB` or `RRGGBB` hex color
+pub fn parse_color(s: &str) -> Result<[u8; 3], String> {
+    let hex = s.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(format!("invalid color '{}': expected #RRGGBB", s));
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| format!("invalid color '{}': {}", s, e))
+    };
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+fn parse_radius(s: &str) -> Result<f32, String> {
+    let radius = s
+        .parse::<f32>()
+        .map_err(|e| format!("invalid radius '{}': {}", s, e))?;
+    if !radius.is_finite() || radius < 0.0 {
+        return Err(format!(
+            "invalid radius '{}': expected a finite value >= 0",
+            s
+        ));
+    }
+    Ok(radius)
+}
+
+impl std::str::FromStr for Background {
+    type Err = String;
+
+    /// Parse `#RRGGBB`, `linear:#FROM,#TO[,ANGLE]`, `radial:#INNER,#OUTER`, `blur:RADIUS[,FEATHER]` or `inpaint`.
+    /// Image backgrounds are built in code.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "inpaint" {
+            return Ok(Background::Inpaint);
+        }
+        let Some((kind, params)) = s.split_once(':') else {
+            return Ok(Background::Color(parse_color(s)?));
+        };
+        let values: Vec<&str> = params.split(',').map(str::trim).collect();
+
+        match (kind, values.as_slice()) {
+            ("linear", [from, to]) => Ok(Background::LinearGradient {
+                from: parse_color(from)?,
+                to: parse_color(to)?,
+                angle: 90.0,
+            }),
+            ("linear", [from, to, angle]) => Ok(Background::LinearGradient {
+                from: parse_color(from)?,
+                to: parse_color(to)?,
+                angle: angle
+                    .parse::<f32>()
+                    .map_err(|e| format!("invalid angle '{}': {}", angle, e))?,
+            }),
+            ("radial", [inner, outer]) => Ok(Background::RadialGradient {
+                inner: parse_color(inner)?,
+                outer: parse_color(outer)?,
+            }),
+            ("blur", [radius]) => Ok(Background::Blur {
+                radius: parse_radius(radius)?,
+                feather: 2.0,
+            }),
+            ("blur", [radius, feather]) => Ok(Background::Blur {
+                radius: parse_radius(radius)?,
+                feather: parse_radius(feather)?,
+            }),
+            _ => Err(format!(
+                "invalid background '{}': expected #RRGGBB, linear:#FROM,#TO[,ANGLE], radial:#INNER,#OUTER, blur:RADIUS[,FEATHER] or inpaint",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blur_samples_the_scene_not_the_cutout() {
+        // Fully transparent red scene; the cutout has black in its transparent pixels,
+        // as the sticker outline leaves it
+        let scene = RgbaImage::from_pixel(8, 8, Rgba([200, 0, 0, 0]));
+        let cutout = RgbaImage::from_pixel(8, 8, Rgba([0, 0, 0, 0]));
+        let background = Background::Blur {
+            radius: 2.0,
+            feather: 0.0,
+        };
+
+        let out = replace_background(&cutout, &scene, &background);
+        assert!(out.pixels().all(|p| *p == Rgb([200, 0, 0])));
+    }
+
+    #[test]
+    fn parses_blur() {
+        assert!(matches!(
+            "blur:4".parse::<Background>(),
+            Ok(Background::Blur { radius, feather }) if radius == 4.0 && feather == 2.0
+        ));
+        assert!(matches!(
+            "blur:4,0".parse::<Background>(),
+            Ok(Background::Blur { radius, feather }) if radius == 4.0 && feather == 0.0
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_blur_radius() {
+        for s in [
+            "blur:NaN",
+            "blur:inf",
+            "blur:-1",
+            "blur:4,NaN",
+            "blur:4,-0.5",
+            "blur:x",
+        ] {
+            assert!(s.parse::<Background>().is_err(), "{}", s);
+        }
+    }
+}
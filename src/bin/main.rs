@@ -1,35 +1,74 @@
 use clap::Parser;
-use image::{DynamicImage, open};
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, RgbImage, RgbaImage, open};
+use rembg_rs::background::Background;
 use rembg_rs::cli::cli::Args;
 use rembg_rs::compress_png::compress_png;
+use rembg_rs::hints::{HintRegion, MaskHint};
+use rembg_rs::load::load_image;
 use rembg_rs::manager::ModelManager;
 use rembg_rs::options::RemovalOptionsBuilder;
-use rembg_rs::rembg::rembg;
+use rembg_rs::profile::ModelProfile;
+use rembg_rs::rect::Rect;
+use rembg_rs::rembg::{rembg, rembg_chroma_key, rembg_flood_fill};
+use rembg_rs::result::RemovalResult;
 use std::path::Path;
 use std::process;
 
+/// Progress output goes to stderr when stdout carries JSON
+macro_rules! status {
+    ($json:expr, $($arg:tt)*) => {
+        if $json {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
 fn main() {
     let args = Args::parse();
 
-    println!("🎨 rembg-rs - Background Removal Tool");
-    println!("Input: {:?}", args.input);
-    println!("Output: {:?}", args.output);
-    println!("Model: {}", args.model);
-    println!();
+    status!(args.json, "🎨 rembg-rs - Background Removal Tool");
+    status!(args.json, "Input: {:?}", args.input);
+    status!(args.json, "Output: {:?}", args.output);
+    match (&args.chroma_key, &args.flood_fill) {
+        (Some(key), _) => status!(args.json, "Chroma key: {:?}", key.color),
+        (None, Some(fill)) => match fill.color {
+            Some(color) => status!(args.json, "Flood fill: {:?}", color),
+            None => status!(args.json, "Flood fill: auto-detected border color"),
+        },
+        (None, None) => status!(args.json, "Model: {}", args.model),
+    }
+    status!(args.json, "");
 
-    let manager = match ModelManager::from_file(Path::new(&args.model)) {
-        Ok(m) => m,
-        Err(e) => {
-            eprintln!("❌ Failed to manager: {}", e);
-            process::exit(1);
+    // Chroma keying and flood fill need no model
+    let manager = if args.chroma_key.is_none() && args.flood_fill.is_none() {
+        let mut profile = ModelProfile::default();
+        if let Some(normalization) = args.normalization {
+            profile = profile.with_normalization(normalization);
         }
-    };
+        if let Some(scaling) = args.input_scaling {
+            profile = profile.with_scaling(scaling);
+        }
+
+        let manager = match ModelManager::from_file_with_profile(Path::new(&args.model), profile) {
+            Ok(m) => m,
+            Err(e) => {
+                eprintln!("❌ Failed to manager: {}", e);
+                process::exit(1);
+            }
+        };
 
-    println!("✅ Model loaded\n");
+        status!(args.json, "✅ Model loaded\n");
+        Some(manager)
+    } else {
+        None
+    };
 
     // Load image
-    println!("📂 Loading image...");
-    let img = match open(&args.input) {
+    status!(args.json, "📂 Loading image...");
+    let img = match load_image(&args.input, !args.no_exif_orientation) {
         Ok(img) => img,
         Err(e) => {
             eprintln!("❌ Failed to load image: {}", e);
@@ -37,18 +76,71 @@ fn main() {
         }
     };
 
+    // Manual keep/remove regions, removals win over keeps
+    let mut hints: Vec<MaskHint> = Vec::new();
+    for rect in &args.keep {
+        hints.push(MaskHint::keep(HintRegion::Rect(*rect)));
+    }
+    for path in &args.keep_mask {
+        hints.push(MaskHint::keep(HintRegion::Mask(load_hint_mask(path))));
+    }
+    for rect in &args.remove {
+        hints.push(MaskHint::remove(HintRegion::Rect(*rect)));
+    }
+    for path in &args.remove_mask {
+        hints.push(MaskHint::remove(HintRegion::Mask(load_hint_mask(path))));
+    }
+
+    let background = match &args.background_image {
+        Some(path) => match load_image(path, !args.no_exif_orientation) {
+            Ok(img) => Some(Background::Image {
+                image: img.to_rgb8(),
+                fit: args.background_fit,
+            }),
+            Err(e) => {
+                eprintln!("❌ Failed to load background image: {}", e);
+                process::exit(1);
+            }
+        },
+        None => args.background.clone(),
+    };
+
     // Configure options
     let options = RemovalOptionsBuilder::default()
         .threshold(args.threshold)
+        .auto_threshold(args.auto_threshold)
         .binary(args.binary)
+        .contour_smoothing(args.smooth)
+        .alpha_curve(args.curve.clone())
         .sticker(args.sticker)
+        .invert(args.invert)
+        .morphology(args.morph.clone())
+        .min_island_area(args.min_island_area)
+        .keep_largest_island(args.keep_largest)
+        .max_hole_area(args.fill_holes)
+        .trimap_band(args.trimap)
+        .split_subjects(args.split)
+        .background(background)
+        .hints(hints)
+        .roi(args.roi)
+        .upsampling(args.upsampling)
+        .color_refine(args.color_refine)
+        .despill(args.despill)
+        .defringe(args.defringe)
+        .input_alpha(args.input_alpha)
         .build()
         .unwrap();
 
-    println!("🖼️  Processing image...");
+    status!(args.json, "🖼️  Processing image...");
 
     // Process the image
-    let result = match rembg(&manager, img, &options) {
+    let processed = match (&manager, &args.chroma_key, &args.flood_fill) {
+        (Some(manager), _, _) => rembg(manager, img, &options),
+        (None, Some(key), _) => rembg_chroma_key(img, key, &options),
+        (None, None, Some(fill)) => rembg_flood_fill(img, fill, &options),
+        (None, None, None) => unreachable!("model is loaded unless a model-free mode is set"),
+    };
+    let result = match processed {
         Ok(result) => result,
         Err(e) => {
             eprintln!("❌ Error: {}", e);
@@ -56,34 +148,63 @@ fn main() {
         }
     };
 
+    if args.auto_threshold {
+        status!(args.json, "🎚️  Selected threshold: {}", result.threshold());
+    }
+
+    if args.color_refine.is_some() && !result.color_refined() {
+        eprintln!("⚠️  The image border is not a uniform color, color refinement was skipped");
+    }
+
+    let confidence = result.confidence();
+    status!(args.json, "📊 Mask confidence: {:.2}", confidence.score);
+    if confidence.empty {
+        if args.invert {
+            eprintln!(
+                "⚠️  The whole image was detected as foreground, the result is fully transparent"
+            );
+        } else {
+            eprintln!("⚠️  No foreground detected, the result is fully transparent");
+        }
+    } else if confidence.full {
+        if args.invert {
+            eprintln!("⚠️  No foreground detected, the result is fully opaque");
+        } else {
+            eprintln!("⚠️  The whole image was detected as foreground");
+        }
+    } else if confidence.score < 0.3 {
+        eprintln!("⚠️  Low mask confidence, the cutout may be inaccurate");
+    }
+
     // Save the result
-    println!("💾 Saving result...");
-    let result_img: DynamicImage = DynamicImage::ImageRgba8(result.image().clone());
-    if option_env!("NONE").is_none() {
-        match compress_png(&result_img) {
-            Ok(bytes) => match std::fs::write(&args.output, bytes) {
-                Ok(_) => {}
-                Err(e) => {
-                    eprintln!("❌ Writing failed: {e}");
-                    process::exit(1);
-                }
-            },
-            Err(e) => {
-                eprintln!("❌ Compression failed: {e}");
-                process::exit(1);
+    status!(args.json, "💾 Saving result...");
+    let saved = match result.composite() {
+        Some(composite) => save_rgb(&args.output, composite, args.quality),
+        None => save_rgba(&args.output, result.image()),
+    };
+    if let Err(e) = saved {
+        eprintln!("❌ {}", e);
+        process::exit(1);
+    }
+
+    // Save separate subjects if requested
+    if args.split {
+        for (i, subject) in result.subjects().iter().enumerate() {
+            // Subjects are always PNG, whatever the output format
+            let subject_path =
+                generate_sibling_path(&args.output, &i.to_string()).with_extension("png");
+            status!(args.json, "✂️  Saving subject {} to: {:?}", i, subject_path);
+
+            if let Err(e) = save_rgba(&subject_path, &subject.image) {
+                eprintln!("⚠️  {}", e);
             }
         }
-    } else {
-        if let Err(e) = result_img.save(&args.output) {
-            eprintln!("❌ Failed to save result: {}", e);
-            process::exit(1);
-        }
     }
 
     // Save mask if requested
     if args.save_mask {
-        let mask_path = generate_mask_path(&args.output);
-        println!("🎭 Saving mask to: {:?}", mask_path);
+        let mask_path = generate_sibling_path(&args.output, "mask");
+        status!(args.json, "🎭 Saving mask to: {:?}", mask_path);
 
         // Save mask as transparent RGBA
         let mask_img = result.mask();
@@ -92,16 +213,68 @@ fn main() {
         }
     }
 
-    println!();
-    println!("✅ Background removed successfully!");
-    println!("Output saved to: {:?}", args.output);
+    // Save trimap if requested
+    if let Some(trimap) = result.trimap() {
+        // Lossless, so matting tools get exact 0/128/255 values
+        let trimap_path = generate_sibling_path(&args.output, "trimap").with_extension("png");
+        status!(args.json, "🗺️  Saving trimap to: {:?}", trimap_path);
+
+        if let Err(e) = trimap.save(&trimap_path) {
+            eprintln!("⚠️  Failed to save trimap: {}", e);
+        }
+    }
+
+    status!(args.json, "");
+    status!(args.json, "✅ Background removed successfully!");
+    status!(args.json, "Output saved to: {:?}", args.output);
     if args.save_mask {
-        println!("🎭 Mask saved alongside output");
+        status!(args.json, "🎭 Mask saved alongside output");
+    }
+
+    if args.json {
+        println!("{}", result_json(&result));
+    }
+}
+
+/// Save an RGBA image, compressed as palette PNG unless built with `NONE` set
+fn save_rgba(path: &Path, image: &RgbaImage) -> Result<(), String> {
+    let img: DynamicImage = DynamicImage::ImageRgba8(image.clone());
+    if option_env!("NONE").is_none() {
+        match compress_png(&img) {
+            Ok(bytes) => match std::fs::write(path, bytes) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(format!("Writing failed: {e}")),
+            },
+            Err(e) => Err(format!("Compression failed: {e}")),
+        }
+    } else {
+        img.save(path)
+            .map_err(|e| format!("Failed to save result: {}", e))
+    }
+}
+
+/// Save an opaque RGB image, as JPEG with the given quality for .jpg/.jpeg paths
+fn save_rgb(path: &Path, image: &RgbImage, quality: u8) -> Result<(), String> {
+    let is_jpeg = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("jpg") || ext.eq_ignore_ascii_case("jpeg"));
+
+    if is_jpeg {
+        let file = std::fs::File::create(path).map_err(|e| format!("Writing failed: {e}"))?;
+        let mut writer = std::io::BufWriter::new(file);
+        JpegEncoder::new_with_quality(&mut writer, quality.clamp(1, 100))
+            .encode_image(image)
+            .map_err(|e| format!("Failed to save result: {}", e))
+    } else {
+        image
+            .save(path)
+            .map_err(|e| format!("Failed to save result: {}", e))
     }
 }
 
-/// Generate mask file path based on output path
-fn generate_mask_path(output_path: &Path) -> std::path::PathBuf {
+/// Generate `<stem>_<suffix>.<ext>` file path next to the output path
+fn generate_sibling_path(output_path: &Path, suffix: &str) -> std::path::PathBuf {
     let file_stem = output_path
         .file_stem()
         .and_then(|s| s.to_str())
@@ -114,5 +287,63 @@ fn generate_mask_path(output_path: &Path) -> std::path::PathBuf {
 
     let parent = output_path.parent().unwrap_or(Path::new("."));
 
-    parent.join(format!("{}_mask.{}", file_stem, extension))
+    parent.join(format!("{}_{}.{}", file_stem, suffix, extension))
+}
+
+/// Load a scribble mask for a hint, exiting on failure
+fn load_hint_mask(path: &Path) -> image::GrayImage {
+    match open(path) {
+        Ok(img) => img.to_luma8(),
+        Err(e) => {
+            eprintln!("❌ Failed to load hint mask {:?}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Serialize result metadata as a JSON object
+fn result_json(result: &RemovalResult) -> String {
+    let geometry = result.geometry();
+    let confidence = result.confidence();
+
+    let bbox = match &geometry.bbox {
+        Some(b) => rect_json(b),
+        None => "null".to_string(),
+    };
+    let centroid = match geometry.centroid {
+        Some((x, y)) => format!("{{\"x\":{:.2},\"y\":{:.2}}}", x, y),
+        None => "null".to_string(),
+    };
+
+    let subjects: Vec<String> = result
+        .subjects()
+        .iter()
+        .map(|s| rect_json(&s.bbox))
+        .collect();
+
+    format!(
+        "{{\"width\":{},\"height\":{},\"threshold\":{},\"bbox\":{},\"centroid\":{},\"area\":{:.2},\"coverage\":{:.6},\
+         \"confidence\":{{\"score\":{:.4},\"uncertain_fraction\":{:.4},\"coverage\":{:.6},\"edge_sharpness\":{:.4},\"empty\":{},\"full\":{}}},\"subjects\":[{}]}}",
+        result.image().width(),
+        result.image().height(),
+        result.threshold(),
+        bbox,
+        centroid,
+        geometry.area,
+        geometry.coverage,
+        confidence.score,
+        confidence.uncertain_fraction,
+        confidence.coverage,
+        confidence.edge_sharpness,
+        confidence.empty,
+        confidence.full,
+        subjects.join(","),
+    )
+}
+
+fn rect_json(rect: &Rect) -> String {
+    format!(
+        "{{\"x\":{},\"y\":{},\"width\":{},\"height\":{}}}",
+        rect.x, rect.y, rect.width, rect.height
+    )
 }
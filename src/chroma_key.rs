@@ -0,0 +1,204 @@
+use crate::background::parse_color;
+use image::{GrayImage, Luma, RgbImage};
+
+/// Color keying without a model: pixels close to the key color become background
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChromaKey {
+    /// Background color to key out
+    pub color: [u8; 3],
+    /// CIELAB distance (ΔE) up to which a pixel is fully background
+    pub tolerance: f32,
+    /// Width of the ΔE ramp from background to foreground after the tolerance.
+    /// Becomes the soft edge of the cutout unless `binary` or a custom curve is set.
+    pub softness: f32,
+}
+
+impl ChromaKey {
+    pub fn new(color: [u8; 3]) -> Self {
+        Self {
+            color,
+            tolerance: 20.0,
+            softness: 10.0,
+        }
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_softness(mut self, softness: f32) -> Self {
+        self.softness = softness;
+        self
+    }
+}
+
+/// Probability mask (0 = background, 255 = foreground) from the perceptual
+/// distance of every pixel to the key color
+pub fn chroma_key_mask(image: &RgbImage, key: &ChromaKey) -> GrayImage {
+    let lut = srgb_to_linear_lut();
+    let reference = rgb_to_lab(key.color, &lut);
+    let tolerance = key.tolerance.max(0.0);
+    let softness = key.softness.max(0.0);
+
+    let (w, h) = image.dimensions();
+    GrayImage::from_fn(w, h, |x, y| {
+        let distance = lab_distance(rgb_to_lab(image.get_pixel(x, y).0, &lut), reference);
+        Luma([key_ramp(distance, tolerance, softness)])
+    })
+}
+
+/// Foreground probability for a color distance: 0 within the tolerance,
+/// rising linearly over the softness to 255
+pub(crate) fn key_ramp(distance: f32, tolerance: f32, softness: f32) -> u8 {
+    let value = if distance <= tolerance {
+        0.0
+    } else if softness <= 0.0 || distance >= tolerance + softness {
+        1.0
+    } else {
+        (distance - tolerance) / softness
+    };
+    (value * 255.0).round() as u8
+}
+
+/// sRGB channel value to linear light
+pub(crate) fn srgb_to_linear_lut() -> [f32; 256] {
+    std::array::from_fn(|i| {
+        let c = i as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    })
+}
+
+/// sRGB (D65) to CIELAB
+pub(crate) fn rgb_to_lab(rgb: [u8; 3], lut: &[f32; 256]) -> [f32; 3] {
+    let [r, g, b] = rgb.map(|c| lut[c as usize]);
+
+    // Linear RGB to XYZ, normalized by the D65 white point
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+    let f = |t: f32| {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// Euclidean CIELAB distance (CIE76 ΔE)
+pub(crate) fn lab_distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+impl std::str::FromStr for ChromaKey {
+    type Err = String;
+
+    /// Parse `#RRGGBB[,TOLERANCE[,SOFTNESS]]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values: Vec<&str> = s.split(',').map(str::trim).collect();
+        let number = |v: &str| {
+            v.parse::<f32>()
+                .map_err(|e| format!("invalid chroma key '{}': {}", s, e))
+        };
+
+        match values.as_slice() {
+            [color] => Ok(ChromaKey::new(parse_color(color)?)),
+            [color, tolerance] => {
+                Ok(ChromaKey::new(parse_color(color)?).with_tolerance(number(tolerance)?))
+            }
+            [color, tolerance, softness] => Ok(ChromaKey::new(parse_color(color)?)
+                .with_tolerance(number(tolerance)?)
+                .with_softness(number(softness)?)),
+            _ => Err(format!(
+                "invalid chroma key '{}': expected #RRGGBB[,TOLERANCE[,SOFTNESS]]",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: [f32; 3], b: [f32; 3]) -> bool {
+        a.iter().zip(b).all(|(x, y)| (x - y).abs() < 0.1)
+    }
+
+    #[test]
+    fn rgb_to_lab_matches_reference_values() {
+        let lut = srgb_to_linear_lut();
+        assert!(close(rgb_to_lab([255, 255, 255], &lut), [100.0, 0.0, 0.0]));
+        assert!(close(rgb_to_lab([0, 0, 0], &lut), [0.0, 0.0, 0.0]));
+        assert!(close(rgb_to_lab([255, 0, 0], &lut), [53.24, 80.09, 67.20]));
+        assert!(close(rgb_to_lab([0, 255, 0], &lut), [87.73, -86.18, 83.18]));
+        assert!(close(
+            rgb_to_lab([0, 0, 255], &lut),
+            [32.30, 79.19, -107.86]
+        ));
+    }
+
+    #[test]
+    fn key_ramp_rises_over_the_softness() {
+        assert_eq!(key_ramp(0.0, 20.0, 10.0), 0);
+        assert_eq!(key_ramp(20.0, 20.0, 10.0), 0);
+        assert_eq!(key_ramp(25.0, 20.0, 10.0), 128);
+        assert_eq!(key_ramp(30.0, 20.0, 10.0), 255);
+        assert_eq!(key_ramp(20.5, 20.0, 0.0), 255);
+    }
+
+    #[test]
+    fn mask_keys_the_color_with_a_soft_edge() {
+        // Key color, gray values ΔE 0, ~5.4 and ~10.8 away, and a far color
+        let image = RgbImage::from_fn(4, 1, |x, _| match x {
+            0 => image::Rgb([128, 128, 128]),
+            1 => image::Rgb([140, 140, 140]),
+            2 => image::Rgb([153, 153, 153]),
+            _ => image::Rgb([255, 0, 0]),
+        });
+        let key = ChromaKey::new([128, 128, 128])
+            .with_tolerance(2.0)
+            .with_softness(6.0);
+        let mask = chroma_key_mask(&image, &key);
+
+        let values: Vec<u8> = mask.pixels().map(|p| p[0]).collect();
+        assert_eq!(values[0], 0);
+        assert!(values[1] > 0 && values[1] < 255, "{:?}", values);
+        assert_eq!(values[2], 255);
+        assert_eq!(values[3], 255);
+    }
+
+    #[test]
+    fn parses_color_tolerance_and_softness() {
+        assert_eq!(
+            "#00ff00".parse::<ChromaKey>(),
+            Ok(ChromaKey::new([0, 255, 0]))
+        );
+        assert_eq!(
+            "#00FF00, 30".parse::<ChromaKey>(),
+            Ok(ChromaKey::new([0, 255, 0]).with_tolerance(30.0))
+        );
+        assert_eq!(
+            "#0000ff,15,4.5".parse::<ChromaKey>(),
+            Ok(ChromaKey::new([0, 0, 255])
+                .with_tolerance(15.0)
+                .with_softness(4.5))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_chroma_keys() {
+        for s in ["", "green", "#00ff0", "#00ff00,x", "#00ff00,1,2,3"] {
+            assert!(s.parse::<ChromaKey>().is_err(), "{}", s);
+        }
+    }
+}
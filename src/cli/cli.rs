@@ -1,3 +1,14 @@
+use crate::alpha_curve::AlphaCurve;
+use crate::background::{Background, ImageFit};
+use crate::chroma_key::ChromaKey;
+use crate::color_refine::ColorRefine;
+use crate::despill::Despill;
+use crate::flood_fill::FloodFill;
+use crate::input_alpha::InputAlpha;
+use crate::morphology::MorphologyOp;
+use crate::profile::{InputScaling, OutputNormalization};
+use crate::rect::Rect;
+use crate::upsample::MaskUpsampling;
 use clap::Parser;
 use std::path::PathBuf;
 
@@ -30,6 +41,23 @@ pub struct Args {
     )]
     pub model: String,
 
+    /// Color keying instead of the model
+    #[arg(
+        long = "chroma-key",
+        value_name = "#RRGGBB[,TOL[,SOFT]]",
+        help = "Key out this color instead of running the model (tolerance and softness in CIELAB ΔE, default 20 and 10; softness gives soft edges unless --binary or --curve is set, --threshold then does not apply)"
+    )]
+    pub chroma_key: Option<ChromaKey>,
+
+    /// Flat background flood fill instead of the model
+    #[arg(
+        long = "flood-fill",
+        value_name = "auto|#RRGGBB[,TOL]",
+        conflicts_with = "chroma_key",
+        help = "Remove the flat background connected to the image edges instead of running the model (auto detects the border color, tolerance in CIELAB ΔE, default 5)"
+    )]
+    pub flood_fill: Option<FloodFill>,
+
     /// Quality for JPEG output (1-100)
     #[arg(
         short = 'q',
@@ -39,6 +67,22 @@ pub struct Args {
     )]
     pub quality: u8,
 
+    /// Ignore EXIF orientation
+    #[arg(
+        long = "no-exif-orientation",
+        help = "Use the pixels as stored instead of rotating them upright by the EXIF orientation tag"
+    )]
+    pub no_exif_orientation: bool,
+
+    /// Existing input transparency
+    #[arg(
+        long = "input-alpha",
+        value_name = "POLICY",
+        default_value = "ignore",
+        help = "Transparency already in the input: ignore, multiply (keep it) or matte:#RRGGBB (flatten before inference)"
+    )]
+    pub input_alpha: InputAlpha,
+
     /// Alpha matting threshold (0-255)
     #[arg(
         short = 't',
@@ -48,6 +92,13 @@ pub struct Args {
     )]
     pub threshold: u8,
 
+    /// Automatic threshold selection
+    #[arg(
+        long = "auto-threshold",
+        help = "Choose the threshold per image from the mask histogram (overrides --threshold)"
+    )]
+    pub auto_threshold: bool,
+
     /// Enable binary mask mode (no transparency, just cut out)
     #[arg(
         short = 'b',
@@ -56,6 +107,23 @@ pub struct Args {
     )]
     pub binary: bool,
 
+    /// Contour smoothing for binary mode
+    #[arg(
+        long = "smooth",
+        value_name = "SIGMA",
+        default_value = "0",
+        help = "Smooth and anti-alias the binary outline with this blur radius (0 = off)"
+    )]
+    pub smooth: f32,
+
+    /// Alpha transfer curve for soft edges
+    #[arg(
+        long = "curve",
+        default_value = "linear",
+        help = "Alpha curve: linear, gamma:G, levels:LOW,HIGH[,GAMMA] or smoothstep:LOW,HIGH"
+    )]
+    pub curve: AlphaCurve,
+
     /// Save mask as separate file
     #[arg(
         short = 's',
@@ -64,7 +132,182 @@ pub struct Args {
     )]
     pub save_mask: bool,
 
+    /// Save trimap as separate file
+    #[arg(
+        long = "trimap",
+        value_name = "BAND",
+        help = "Save a trimap with an unknown band of BAND pixels alongside the output"
+    )]
+    pub trimap: Option<u8>,
+
     /// Save mask as separate file
     #[arg(long = "sticker", help = "Process sticker")]
     pub sticker: bool,
+
+    /// Inverse mode
+    #[arg(
+        long = "invert",
+        help = "Keep the background and remove the subject instead"
+    )]
+    pub invert: bool,
+
+    /// Regions to always keep
+    #[arg(
+        long = "keep",
+        value_name = "X,Y,W,H",
+        help = "Rectangle that is always kept as foreground (repeatable)"
+    )]
+    pub keep: Vec<Rect>,
+
+    /// Regions to always remove
+    #[arg(
+        long = "remove",
+        value_name = "X,Y,W,H",
+        help = "Rectangle that is always removed as background (repeatable)"
+    )]
+    pub remove: Vec<Rect>,
+
+    /// Scribble masks of regions to always keep
+    #[arg(
+        long = "keep-mask",
+        help = "Image whose non-black pixels are always kept as foreground (repeatable)"
+    )]
+    pub keep_mask: Vec<PathBuf>,
+
+    /// Scribble masks of regions to always remove
+    #[arg(
+        long = "remove-mask",
+        help = "Image whose non-black pixels are always removed as background (repeatable)"
+    )]
+    pub remove_mask: Vec<PathBuf>,
+
+    /// Region of interest
+    #[arg(
+        long = "roi",
+        value_name = "X,Y,W,H",
+        help = "Process only this rectangle, everything outside it becomes transparent"
+    )]
+    pub roi: Option<Rect>,
+
+    /// Mask upsampling method
+    #[arg(
+        long = "upsampling",
+        default_value = "lanczos3",
+        help = "Mask upsampling: lanczos3 or bilateral[:SPATIAL,RANGE] (edge-aware, guided by the image)"
+    )]
+    pub upsampling: MaskUpsampling,
+
+    /// How model outputs are turned into probabilities
+    #[arg(
+        long = "normalization",
+        value_enum,
+        help = "Model output normalization (min-max matches python rembg, default: sigmoid)"
+    )]
+    pub normalization: Option<OutputNormalization>,
+
+    /// How input pixels are scaled before the model
+    #[arg(
+        long = "input-scaling",
+        value_enum,
+        help = "Model input scaling (image-max with --normalization min-max matches python rembg, default: fixed)"
+    )]
+    pub input_scaling: Option<InputScaling>,
+
+    /// Morphological clean-up of the alpha mask
+    #[arg(
+        long = "morph",
+        value_name = "OP:RADIUS[:SHAPE]",
+        help = "Morphology step: erode, dilate, open or close with square, diamond or disk kernel (repeatable)"
+    )]
+    pub morph: Vec<MorphologyOp>,
+
+    /// Minimum size of kept foreground components
+    #[arg(
+        long = "min-island-area",
+        default_value = "0",
+        help = "Remove foreground specks smaller than this many pixels (0 = off)"
+    )]
+    pub min_island_area: u32,
+
+    /// Keep only the largest subject
+    #[arg(
+        long = "keep-largest",
+        help = "Keep only the largest foreground component"
+    )]
+    pub keep_largest: bool,
+
+    /// Maximum size of filled holes
+    #[arg(
+        long = "fill-holes",
+        value_name = "MAX_AREA",
+        default_value = "0",
+        help = "Fill enclosed holes up to this many pixels (0 = off)"
+    )]
+    pub fill_holes: u32,
+
+    /// Color based edge refinement
+    #[arg(
+        long = "color-refine",
+        value_name = "BAND[,TOL[,SOFT]]",
+        num_args = 0..=1,
+        default_missing_value = "8",
+        help = "Snap mask edges within BAND pixels to a uniform border background color (tolerance and softness in CIELAB ΔE, default 8 and 8; soft edges need a gradual --curve such as levels:0,255)"
+    )]
+    pub color_refine: Option<ColorRefine>,
+
+    /// Key color spill suppression
+    #[arg(
+        long = "despill",
+        value_name = "KEY[,STRENGTH[,ALGO[,BAND]]]",
+        help = "Remove green/blue (or hue in degrees) spill from edge pixels; ALGO is average or max (default 1.0, average, 2)"
+    )]
+    pub despill: Option<Despill>,
+
+    /// Edge recoloring
+    #[arg(
+        long = "defringe",
+        value_name = "BAND",
+        default_value = "0",
+        help = "Replace edge colors within BAND pixels of the outline with interior colors, removing light or dark rims (0 = off)"
+    )]
+    pub defringe: u8,
+
+    /// Replacement background
+    #[arg(
+        long = "background",
+        value_name = "SPEC",
+        conflicts_with = "background_image",
+        help = "Composite onto #RRGGBB, linear:#FROM,#TO[,ANGLE], radial:#INNER,#OUTER, blur:RADIUS[,FEATHER] (portrait blur) or inpaint (fill removed areas)"
+    )]
+    pub background: Option<Background>,
+
+    /// Replacement background picture
+    #[arg(
+        long = "background-image",
+        help = "Composite onto this image (JPEG output uses --quality)"
+    )]
+    pub background_image: Option<PathBuf>,
+
+    /// Background picture fitting
+    #[arg(
+        long = "background-fit",
+        value_enum,
+        default_value = "cover",
+        help = "How the background image is fitted: cover or contain"
+    )]
+    pub background_fit: ImageFit,
+
+    /// Save every subject separately
+    #[arg(
+        long = "split",
+        help = "Also save each separate object as <name>_0.png, <name>_1.png, ... (largest first)"
+    )]
+    pub split: bool,
+
+    /// Print result metadata as JSON
+    #[arg(
+        long = "json",
+        help = "Print subject geometry and mask confidence as JSON to stdout (progress goes to stderr)"
+    )]
+    pub json: bool,
 }
@@ -0,0 +1,214 @@
+use crate::chroma_key::{key_ramp, lab_distance, rgb_to_lab, srgb_to_linear_lut};
+use crate::flood_fill::dominant_border_color;
+use crate::trimap::{TRIMAP_UNKNOWN, trimap};
+use image::{GrayImage, RgbImage};
+
+/// Share of border pixels that must match the border color for the background
+/// to count as near-uniform
+const MIN_UNIFORM_SHARE: f32 = 0.9;
+
+/// Probability that splits the model mask into foreground and background
+const MODEL_SPLIT: u8 = 128;
+
+/// Sharpen model mask edges against a near-uniform background color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorRefine {
+    /// Pixels around the model outline, on either side, that may be changed
+    pub band: u8,
+    /// CIELAB distance (ΔE) to the background color up to which a pixel is background
+    pub tolerance: f32,
+    /// Width of the ΔE ramp from background to foreground after the tolerance.
+    /// The ramp passes through the alpha curve like the model mask, so it only gives
+    /// soft edges with a gradual curve such as `levels`; the default `Linear` curve cuts
+    /// it at the threshold and softness then only moves the edge.
+    pub softness: f32,
+}
+
+impl Default for ColorRefine {
+    fn default() -> Self {
+        Self {
+            band: 8,
+            tolerance: 8.0,
+            softness: 8.0,
+        }
+    }
+}
+
+/// Replace the model probability near its outline with the color distance to
+/// the background detected on the image border.
+///
+/// The model decides what the subject is, the color decides where exactly its edge runs.
+/// Returns `false` and leaves the mask untouched when the border is not uniform enough.
+pub fn refine_with_background_color(
+    probability: &mut GrayImage,
+    image: &RgbImage,
+    refine: &ColorRefine,
+) -> bool {
+    let (w, h) = image.dimensions();
+    if w < 2 || h < 2 {
+        return false;
+    }
+
+    let lut = srgb_to_linear_lut();
+    let background = rgb_to_lab(dominant_border_color(image), &lut);
+    let distance =
+        |x: u32, y: u32| lab_distance(rgb_to_lab(image.get_pixel(x, y).0, &lut), background);
+
+    // Only trust the color where the border actually is one color
+    let limit = refine.tolerance + refine.softness;
+    let border: Vec<(u32, u32)> = (0..w)
+        .flat_map(|x| [(x, 0), (x, h - 1)])
+        .chain((1..h - 1).flat_map(|y| [(0, y), (w - 1, y)]))
+        .collect();
+    let matching = border
+        .iter()
+        .filter(|&&(x, y)| distance(x, y) <= limit)
+        .count();
+    if (matching as f32) < border.len() as f32 * MIN_UNIFORM_SHARE {
+        return false;
+    }
+
+    let edges = trimap(probability, MODEL_SPLIT, refine.band);
+    for (x, y, pixel) in probability.enumerate_pixels_mut() {
+        if edges.get_pixel(x, y)[0] == TRIMAP_UNKNOWN {
+            pixel.0[0] = key_ramp(distance(x, y), refine.tolerance, refine.softness);
+        }
+    }
+    true
+}
+
+impl std::str::FromStr for ColorRefine {
+    type Err = String;
+
+    /// Parse `BAND[,TOLERANCE[,SOFTNESS]]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values: Vec<&str> = s.split(',').map(str::trim).collect();
+        let invalid = |e: &dyn std::fmt::Display| format!("invalid color refine '{}': {}", s, e);
+        let number = |v: &str| v.parse::<f32>().map_err(|e| invalid(&e));
+
+        let mut refine = ColorRefine::default();
+        match values.as_slice() {
+            [band, rest @ ..] if rest.len() <= 2 => {
+                refine.band = band.parse::<u8>().map_err(|e| invalid(&e))?;
+                if let Some(tolerance) = rest.first() {
+                    refine.tolerance = number(tolerance)?;
+                }
+                if let Some(softness) = rest.get(1) {
+                    refine.softness = number(softness)?;
+                }
+                Ok(refine)
+            }
+            _ => Err(invalid(&"expected BAND[,TOLERANCE[,SOFTNESS]]")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Luma, Rgb};
+
+    /// Dark 10x10 square on white in a 30x30 image
+    fn product_shot() -> RgbImage {
+        RgbImage::from_fn(30, 30, |x, y| {
+            if (10..20).contains(&x) && (10..20).contains(&y) {
+                Rgb([40, 30, 20])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        })
+    }
+
+    /// Model mask that overshoots the square by two pixels with a soft edge
+    fn loose_mask() -> GrayImage {
+        GrayImage::from_fn(30, 30, |x, y| {
+            let inside = |lo: u32, hi: u32| (lo..hi).contains(&x) && (lo..hi).contains(&y);
+            Luma([if inside(9, 21) {
+                255
+            } else if inside(8, 22) {
+                180
+            } else {
+                0
+            }])
+        })
+    }
+
+    #[test]
+    fn snaps_the_edge_to_the_background_color() {
+        let mut probability = loose_mask();
+        assert!(refine_with_background_color(
+            &mut probability,
+            &product_shot(),
+            &ColorRefine::default()
+        ));
+
+        // The overshoot becomes background, the square itself stays foreground
+        assert_eq!(probability.get_pixel(8, 15)[0], 0);
+        assert_eq!(probability.get_pixel(9, 15)[0], 0);
+        assert_eq!(probability.get_pixel(10, 15)[0], 255);
+        assert_eq!(probability.get_pixel(15, 15)[0], 255);
+        assert_eq!(probability.get_pixel(1, 1)[0], 0);
+    }
+
+    #[test]
+    fn band_limits_the_changes() {
+        // A background-colored pixel deep inside the model foreground is outside the band
+        let mut image = product_shot();
+        image.put_pixel(15, 15, Rgb([255, 255, 255]));
+        let mut probability = loose_mask();
+        let refine = ColorRefine {
+            band: 2,
+            ..ColorRefine::default()
+        };
+
+        assert!(refine_with_background_color(
+            &mut probability,
+            &image,
+            &refine
+        ));
+        assert_eq!(probability.get_pixel(15, 15)[0], 255);
+        assert_eq!(probability.get_pixel(9, 15)[0], 0);
+    }
+
+    #[test]
+    fn skips_a_non_uniform_border() {
+        // Left half of the border is black, right half white
+        let image = RgbImage::from_fn(30, 30, |x, _| {
+            if x < 15 {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        });
+        let mut probability = loose_mask();
+
+        assert!(!refine_with_background_color(
+            &mut probability,
+            &image,
+            &ColorRefine::default()
+        ));
+        assert_eq!(probability, loose_mask());
+    }
+
+    #[test]
+    fn parses_band_tolerance_and_softness() {
+        assert_eq!(
+            "4".parse(),
+            Ok(ColorRefine {
+                band: 4,
+                ..ColorRefine::default()
+            })
+        );
+        assert_eq!(
+            "4, 12,3.5".parse(),
+            Ok(ColorRefine {
+                band: 4,
+                tolerance: 12.0,
+                softness: 3.5,
+            })
+        );
+        for s in ["", "x", "300", "4,a", "4,1,2,3"] {
+            assert!(s.parse::<ColorRefine>().is_err(), "{}", s);
+        }
+    }
+}
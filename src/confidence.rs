@@ -0,0 +1,149 @@
+use image::{GrayImage, Luma};
+
+/// Probability range treated as a soft transition rather than a decision
+const SOFT_LOW: u8 = 16;
+const SOFT_HIGH: u8 = 239;
+
+/// Coverage below which a mask counts as empty (and above which as full)
+const EMPTY_COVERAGE: f32 = 0.001;
+
+/// How trustworthy a probability mask looks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaskConfidence {
+    /// Overall score in [0, 1], low values mean the cutout is likely bad
+    pub score: f32,
+    /// Share of uncertain pixels (probability in the middle third) relative to the foreground area
+    pub uncertain_fraction: f32,
+    /// Share of the image kept in the cutout
+    pub coverage: f32,
+    /// 1 for outlines at most one pixel wide, falls as the soft transition widens
+    pub edge_sharpness: f32,
+    /// The cutout is fully transparent
+    pub empty: bool,
+    /// The cutout is fully opaque
+    pub full: bool,
+}
+
+/// Per-pixel uncertainty: 255 at probability 0.5, 0 at probability 0 or 1
+pub fn uncertainty_map(probability: &GrayImage) -> GrayImage {
+    let (w, h) = probability.dimensions();
+    GrayImage::from_fn(w, h, |x, y| {
+        let p = probability.get_pixel(x, y)[0] as i32;
+        Luma([(255 - (2 * p - 255).abs()) as u8])
+    })
+}
+
+pub fn mask_confidence(probability: &GrayImage, threshold: u8) -> MaskConfidence {
+    confidence_of(probability, |p| p >= threshold)
+}
+
+/// Confidence of the inverted cutout, which keeps the pixels below `threshold`
+pub fn inverted_mask_confidence(probability: &GrayImage, threshold: u8) -> MaskConfidence {
+    confidence_of(probability, |p| p < threshold)
+}
+
+fn confidence_of(probability: &GrayImage, kept: impl Fn(u8) -> bool) -> MaskConfidence {
+    let (w, h) = probability.dimensions();
+    let total = (w as u64 * h as u64).max(1);
+
+    let inside = |x: u32, y: u32| kept(probability.get_pixel(x, y)[0]);
+
+    let mut foreground = 0u64;
+    let mut uncertain = 0u64;
+    let mut soft = 0u64;
+    let mut outline = 0u64;
+
+    for (x, y, pixel) in probability.enumerate_pixels() {
+        let p = pixel[0];
+        if kept(p) {
+            foreground += 1;
+        }
+        if (85..=170).contains(&p) {
+            uncertain += 1;
+        }
+        if (SOFT_LOW..=SOFT_HIGH).contains(&p) {
+            soft += 1;
+        }
+
+        // Foreground pixel with a 4-neighbour outside
+        if inside(x, y) {
+            let edge = (x > 0 && !inside(x - 1, y))
+                || (y > 0 && !inside(x, y - 1))
+                || (x + 1 < w && !inside(x + 1, y))
+                || (y + 1 < h && !inside(x, y + 1));
+            if edge {
+                outline += 1;
+            }
+        }
+    }
+
+    let coverage = foreground as f32 / total as f32;
+    let empty = coverage < EMPTY_COVERAGE;
+    let full = coverage > 1.0 - EMPTY_COVERAGE;
+
+    let uncertain_fraction = (uncertain as f32 / foreground.max(1) as f32).min(1.0);
+
+    // Average width of the soft transition measured across the outline
+    let edge_sharpness = if outline > 0 {
+        let width = soft as f32 / outline as f32;
+        1.0 / width.max(1.0)
+    } else {
+        0.0
+    };
+
+    let score = if empty || full {
+        0.0
+    } else {
+        (edge_sharpness * (1.0 - uncertain_fraction)).clamp(0.0, 1.0)
+    };
+
+    MaskConfidence {
+        score,
+        uncertain_fraction,
+        coverage,
+        edge_sharpness,
+        empty,
+        full,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 10x10 mask with a 4x4 foreground square and a soft ring around it
+    fn soft_square() -> GrayImage {
+        GrayImage::from_fn(10, 10, |x, y| {
+            let inside = |lo: u32, hi: u32| (lo..hi).contains(&x) && (lo..hi).contains(&y);
+            Luma([if inside(3, 7) {
+                255
+            } else if inside(2, 8) {
+                128
+            } else {
+                0
+            }])
+        })
+    }
+
+    #[test]
+    fn inverted_confidence_describes_the_kept_background() {
+        let mask = soft_square();
+        let normal = mask_confidence(&mask, 160);
+        let inverted = inverted_mask_confidence(&mask, 160);
+
+        assert!((normal.coverage + inverted.coverage - 1.0).abs() < 1e-6);
+        // The 20 uncertain ring pixels against 16 (capped at 1) and 84 kept pixels
+        assert_eq!(normal.uncertain_fraction, 1.0);
+        assert!((inverted.uncertain_fraction - 20.0 / 84.0).abs() < 1e-6);
+        assert!(inverted.score > normal.score);
+    }
+
+    #[test]
+    fn inverted_confidence_swaps_empty_and_full() {
+        let empty = GrayImage::new(8, 8);
+        assert!(mask_confidence(&empty, 128).empty);
+        let inverted = inverted_mask_confidence(&empty, 128);
+        assert!(inverted.full && !inverted.empty);
+        assert_eq!(inverted.score, 0.0);
+    }
+}
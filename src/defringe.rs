@@ -0,0 +1,157 @@
+use crate::trimap::{TRIMAP_FOREGROUND, TRIMAP_UNKNOWN, trimap};
+use image::{GrayImage, Luma, RgbaImage};
+use std::collections::VecDeque;
+
+/// Replace the RGB of edge pixels with colors grown outwards from the solid interior.
+///
+/// Pixels that are opaque and more than `band` pixels away from any non-opaque pixel
+/// are the interior. Visible pixels within `band` of the opaque outline, on either side,
+/// get the average color of their already recolored neighbours, layer by layer from the
+/// interior, so a light or dark rim left by the old background disappears. Pixels
+/// further out, like a wide soft shadow, and alpha are not changed.
+pub fn defringe(image: &mut RgbaImage, band: u8) {
+    let (w, h) = image.dimensions();
+    let alpha = GrayImage::from_fn(w, h, |x, y| Luma([image.get_pixel(x, y)[3]]));
+    let regions = trimap(&alpha, 255, band);
+
+    let idx = |x: u32, y: u32| (y * w + x) as usize;
+    let mut known: Vec<bool> = regions
+        .pixels()
+        .map(|p| p[0] == TRIMAP_FOREGROUND)
+        .collect();
+    let editable = |x: u32, y: u32| {
+        alpha.get_pixel(x, y)[0] > 0 && regions.get_pixel(x, y)[0] == TRIMAP_UNKNOWN
+    };
+
+    // Edge pixels in order of distance from the interior
+    let mut queued = known.clone();
+    let mut queue = VecDeque::new();
+    for y in 0..h {
+        for x in 0..w {
+            if known[idx(x, y)] {
+                queue.push_back((x, y));
+            }
+        }
+    }
+    let mut order = Vec::new();
+    while let Some((x, y)) = queue.pop_front() {
+        for (nx, ny) in neighbours(x, y, w, h) {
+            let i = idx(nx, ny);
+            if !queued[i] && editable(nx, ny) {
+                queued[i] = true;
+                order.push((nx, ny));
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    for (x, y) in order {
+        let mut sum = [0u32; 3];
+        let mut count = 0;
+        for (nx, ny) in neighbours(x, y, w, h) {
+            if known[idx(nx, ny)] {
+                let p = image.get_pixel(nx, ny);
+                for c in 0..3 {
+                    sum[c] += p[c] as u32;
+                }
+                count += 1;
+            }
+        }
+
+        let pixel = image.get_pixel_mut(x, y);
+        for c in 0..3 {
+            pixel[c] = ((sum[c] + count / 2) / count) as u8;
+        }
+        known[idx(x, y)] = true;
+    }
+}
+
+/// 8-neighbourhood inside the image
+fn neighbours(x: u32, y: u32, w: u32, h: u32) -> impl Iterator<Item = (u32, u32)> {
+    (-1i64..=1)
+        .flat_map(|dy| (-1i64..=1).map(move |dx| (dx, dy)))
+        .filter(|&(dx, dy)| dx != 0 || dy != 0)
+        .filter_map(move |(dx, dy)| {
+            let nx = x as i64 + dx;
+            let ny = y as i64 + dy;
+            if nx >= 0 && ny >= 0 && nx < w as i64 && ny < h as i64 {
+                Some((nx as u32, ny as u32))
+            } else {
+                None
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const SUBJECT: Rgba<u8> = Rgba([10, 20, 30, 255]);
+    const RIM: [u8; 3] = [250, 250, 250];
+
+    /// Dark 12x12 subject on transparent white with a light top rim:
+    /// an opaque row and a semi-transparent row above it
+    fn product_shot() -> RgbaImage {
+        let mut img = RgbaImage::from_pixel(20, 20, Rgba([255, 255, 255, 0]));
+        for y in 4..16 {
+            for x in 4..16 {
+                img.put_pixel(x, y, SUBJECT);
+            }
+        }
+        for x in 4..16 {
+            img.put_pixel(x, 4, Rgba([RIM[0], RIM[1], RIM[2], 255]));
+            img.put_pixel(x, 3, Rgba([RIM[0], RIM[1], RIM[2], 100]));
+        }
+        img
+    }
+
+    #[test]
+    fn recolors_the_rim_from_the_interior() {
+        let mut img = product_shot();
+        defringe(&mut img, 2);
+
+        assert_eq!(*img.get_pixel(8, 4), SUBJECT);
+        assert_eq!(*img.get_pixel(8, 3), Rgba([10, 20, 30, 100]));
+    }
+
+    #[test]
+    fn keeps_alpha_and_transparent_pixels() {
+        let mut img = product_shot();
+        defringe(&mut img, 2);
+
+        assert_eq!(*img.get_pixel(0, 0), Rgba([255, 255, 255, 0]));
+        for (before, after) in product_shot().pixels().zip(img.pixels()) {
+            assert_eq!(before[3], after[3]);
+        }
+    }
+
+    #[test]
+    fn wide_soft_region_outside_the_band_keeps_its_color() {
+        // Opaque block, then a 25px wide strip at alpha 50 to the right of it
+        let mut img = RgbaImage::from_pixel(50, 20, Rgba([255, 255, 255, 0]));
+        for y in 0..20 {
+            for x in 0..15 {
+                img.put_pixel(x, y, SUBJECT);
+            }
+            for x in 15..40 {
+                img.put_pixel(x, y, Rgba([RIM[0], RIM[1], RIM[2], 50]));
+            }
+        }
+        defringe(&mut img, 2);
+
+        // Within the band the strip is recolored, further out it is left alone
+        assert_eq!(*img.get_pixel(15, 10), Rgba([10, 20, 30, 50]));
+        assert_eq!(*img.get_pixel(16, 10), Rgba([10, 20, 30, 50]));
+        assert_eq!(*img.get_pixel(18, 10), Rgba([RIM[0], RIM[1], RIM[2], 50]));
+        assert_eq!(*img.get_pixel(35, 10), Rgba([RIM[0], RIM[1], RIM[2], 50]));
+    }
+
+    #[test]
+    fn band_limits_the_interior() {
+        // With a band as wide as the subject nothing is solid interior, so nothing changes
+        let mut img = product_shot();
+        defringe(&mut img, 8);
+        assert_eq!(img, product_shot());
+    }
+}
@@ -0,0 +1,233 @@
+use crate::trimap::{TRIMAP_UNKNOWN, trimap};
+use image::{GrayImage, RgbaImage};
+
+/// How much of the key channel counts as spill
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpillAlgorithm {
+    /// Limit the key channel to the average of the other two (gentle)
+    #[default]
+    Average,
+    /// Limit the key channel to the larger of the other two (strong)
+    Max,
+}
+
+/// Remove key color spill from the edge of a cutout
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Despill {
+    /// Hue of the key color in degrees (120 = green, 240 = blue)
+    pub hue: f32,
+    /// Share of the detected spill that is removed, 0 to 1
+    pub strength: f32,
+    pub algorithm: SpillAlgorithm,
+    /// Opaque pixels within this distance of the alpha edge are treated too
+    pub band: u8,
+}
+
+impl Default for Despill {
+    fn default() -> Self {
+        Self {
+            hue: 120.0,
+            strength: 1.0,
+            algorithm: SpillAlgorithm::Average,
+            band: 2,
+        }
+    }
+}
+
+/// Suppress the key color in the RGB of semi-transparent pixels and opaque pixels near the edge.
+///
+/// Colors are rotated around the gray axis so the key hue lands on green, the green
+/// excess over the other channels is reduced, and the color is rotated back.
+pub fn despill(image: &mut RgbaImage, despill: &Despill) {
+    let (w, h) = image.dimensions();
+    let alpha = GrayImage::from_fn(w, h, |x, y| image::Luma([image.get_pixel(x, y)[3]]));
+    let edges = trimap(&alpha, 255, despill.band);
+
+    let to_green = hue_rotation(120.0 - despill.hue);
+    let back = hue_rotation(despill.hue - 120.0);
+    let strength = despill.strength.clamp(0.0, 1.0);
+
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let a = pixel[3];
+        if a == 0 || (a == 255 && edges.get_pixel(x, y)[0] != TRIMAP_UNKNOWN) {
+            continue;
+        }
+
+        let [r, g, b] = rotate(&to_green, [pixel[0], pixel[1], pixel[2]].map(|c| c as f32));
+        let limit = match despill.algorithm {
+            SpillAlgorithm::Average => (r + b) / 2.0,
+            SpillAlgorithm::Max => r.max(b),
+        };
+        let spill = (g - limit).max(0.0);
+        let rgb = rotate(&back, [r, g - spill * strength, b]);
+
+        for c in 0..3 {
+            pixel[c] = rgb[c].round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+/// Rotation of RGB vectors by `degrees` around the gray axis (a hue shift)
+fn hue_rotation(degrees: f32) -> [[f32; 3]; 3] {
+    let (s, c) = degrees.to_radians().sin_cos();
+    let k = (1.0 - c) / 3.0;
+    let q = s / 3f32.sqrt();
+    [
+        [c + k, k - q, k + q],
+        [k + q, c + k, k - q],
+        [k - q, k + q, c + k],
+    ]
+}
+
+fn rotate(m: &[[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+impl std::str::FromStr for SpillAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "average" => Ok(SpillAlgorithm::Average),
+            "max" => Ok(SpillAlgorithm::Max),
+            _ => Err(format!(
+                "invalid spill algorithm '{}': expected average or max",
+                s
+            )),
+        }
+    }
+}
+
+impl std::str::FromStr for Despill {
+    type Err = String;
+
+    /// Parse `KEY[,STRENGTH[,ALGORITHM[,BAND]]]` where KEY is `green`, `blue` or a hue in degrees
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values: Vec<&str> = s.split(',').map(str::trim).collect();
+        if values.len() > 4 {
+            return Err(format!(
+                "invalid despill '{}': expected KEY[,STRENGTH[,ALGORITHM[,BAND]]]",
+                s
+            ));
+        }
+
+        let mut despill = Despill {
+            hue: match values[0] {
+                "green" => 120.0,
+                "blue" => 240.0,
+                hue => hue
+                    .parse::<f32>()
+                    .map_err(|e| format!("invalid despill key '{}': {}", hue, e))?,
+            },
+            ..Despill::default()
+        };
+        if let Some(strength) = values.get(1) {
+            despill.strength = strength
+                .parse::<f32>()
+                .map_err(|e| format!("invalid despill strength '{}': {}", strength, e))?;
+        }
+        if let Some(algorithm) = values.get(2) {
+            despill.algorithm = algorithm.parse()?;
+        }
+        if let Some(band) = values.get(3) {
+            despill.band = band
+                .parse::<u8>()
+                .map_err(|e| format!("invalid despill band '{}': {}", band, e))?;
+        }
+        Ok(despill)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn close(a: Rgba<u8>, b: [u8; 4]) -> bool {
+        (0..4).all(|c| (a[c] as i32 - b[c] as i32).abs() <= 1)
+    }
+
+    #[test]
+    fn neutralises_green_spill() {
+        let mut img = RgbaImage::from_pixel(4, 4, Rgba([100, 200, 100, 128]));
+        despill(&mut img, &Despill::default());
+        assert_eq!(*img.get_pixel(0, 0), Rgba([100, 100, 100, 128]));
+    }
+
+    #[test]
+    fn average_and_max_limit_differently() {
+        let spill = Rgba([60, 200, 140, 128]);
+
+        let mut average = RgbaImage::from_pixel(1, 1, spill);
+        despill(&mut average, &Despill::default());
+        assert_eq!(average.get_pixel(0, 0)[1], 100);
+
+        let mut max = RgbaImage::from_pixel(1, 1, spill);
+        let settings = Despill {
+            algorithm: SpillAlgorithm::Max,
+            ..Despill::default()
+        };
+        despill(&mut max, &settings);
+        assert_eq!(max.get_pixel(0, 0)[1], 140);
+    }
+
+    #[test]
+    fn removes_blue_spill_via_hue_rotation() {
+        let mut img = RgbaImage::from_pixel(4, 4, Rgba([100, 80, 200, 128]));
+        let settings = Despill {
+            hue: 240.0,
+            algorithm: SpillAlgorithm::Max,
+            ..Despill::default()
+        };
+        despill(&mut img, &settings);
+        assert!(close(*img.get_pixel(0, 0), [100, 80, 100, 128]));
+    }
+
+    #[test]
+    fn strength_scales_the_correction() {
+        let mut img = RgbaImage::from_pixel(1, 1, Rgba([100, 200, 100, 128]));
+        let settings = Despill {
+            strength: 0.5,
+            ..Despill::default()
+        };
+        despill(&mut img, &settings);
+        assert_eq!(img.get_pixel(0, 0)[1], 150);
+    }
+
+    #[test]
+    fn leaves_solid_interior_and_transparent_pixels() {
+        let mut img = RgbaImage::from_pixel(12, 12, Rgba([100, 200, 100, 0]));
+        for y in 2..10 {
+            for x in 2..10 {
+                img.put_pixel(x, y, Rgba([100, 200, 100, 255]));
+            }
+        }
+        despill(&mut img, &Despill::default());
+
+        // Edge of the opaque area is treated, the interior and background are not
+        assert_eq!(img.get_pixel(2, 5)[1], 100);
+        assert_eq!(img.get_pixel(6, 6)[1], 200);
+        assert_eq!(img.get_pixel(0, 0)[1], 200);
+    }
+
+    #[test]
+    fn parses_despill_specs() {
+        assert_eq!("green".parse::<Despill>(), Ok(Despill::default()));
+        assert_eq!(
+            "blue,0.5,max,4".parse::<Despill>(),
+            Ok(Despill {
+                hue: 240.0,
+                strength: 0.5,
+                algorithm: SpillAlgorithm::Max,
+                band: 4,
+            })
+        );
+        assert_eq!("60".parse::<Despill>().map(|d| d.hue), Ok(60.0));
+
+        assert!("red".parse::<Despill>().is_err());
+        assert!("green,x".parse::<Despill>().is_err());
+        assert!("green,1,median".parse::<Despill>().is_err());
+        assert!("green,1,max,-2".parse::<Despill>().is_err());
+        assert!("green,1,max,2,3".parse::<Despill>().is_err());
+    }
+}
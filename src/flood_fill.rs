@@ -0,0 +1,205 @@
+use crate::background::parse_color;
+use crate::chroma_key::{lab_distance, rgb_to_lab, srgb_to_linear_lut};
+use image::{GrayImage, Luma, RgbImage};
+use std::collections::{HashMap, VecDeque};
+
+/// Flat background removal: flood fill from the image edges
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloodFill {
+    /// Background color, detected from the image border when `None`
+    pub color: Option<[u8; 3]>,
+    /// CIELAB distance (ΔE) up to which a pixel still counts as background
+    pub tolerance: f32,
+}
+
+impl Default for FloodFill {
+    fn default() -> Self {
+        Self {
+            color: None,
+            tolerance: 5.0,
+        }
+    }
+}
+
+/// Most common color along the image border.
+///
+/// Colors are binned with 4 bits per channel so slight noise does not split the
+/// vote, and the winner is the average of the border pixels in the top bin.
+pub fn dominant_border_color(image: &RgbImage) -> [u8; 3] {
+    let (w, h) = image.dimensions();
+    if w == 0 || h == 0 {
+        return [0, 0, 0];
+    }
+
+    let mut bins: HashMap<[u8; 3], (u32, [u64; 3])> = HashMap::new();
+    for (x, y) in border(w, h) {
+        let pixel = image.get_pixel(x, y).0;
+        let entry = bins.entry(pixel.map(|c| c >> 4)).or_insert((0, [0; 3]));
+        entry.0 += 1;
+        for (sum, value) in entry.1.iter_mut().zip(pixel) {
+            *sum += value as u64;
+        }
+    }
+
+    let (count, sum) = bins
+        .into_values()
+        .max_by_key(|(count, _)| *count)
+        .unwrap_or((1, [0; 3]));
+    sum.map(|s| (s as f32 / count as f32).round() as u8)
+}
+
+/// Mask (0 = background, 255 = foreground) of the background connected to the edges.
+///
+/// Regions of the background color enclosed by the subject stay opaque.
+pub fn flood_fill_mask(image: &RgbImage, fill: &FloodFill) -> GrayImage {
+    let (w, h) = image.dimensions();
+    let mut mask = GrayImage::from_pixel(w, h, Luma([255]));
+    if w == 0 || h == 0 {
+        return mask;
+    }
+
+    let lut = srgb_to_linear_lut();
+    let reference = rgb_to_lab(
+        fill.color.unwrap_or_else(|| dominant_border_color(image)),
+        &lut,
+    );
+    let is_background = |x: u32, y: u32| {
+        lab_distance(rgb_to_lab(image.get_pixel(x, y).0, &lut), reference) <= fill.tolerance
+    };
+
+    let mut queue = VecDeque::new();
+    for (x, y) in border(w, h) {
+        if mask.get_pixel(x, y)[0] != 0 && is_background(x, y) {
+            mask.put_pixel(x, y, Luma([0]));
+            queue.push_back((x, y));
+        }
+    }
+
+    while let Some((x, y)) = queue.pop_front() {
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for (nx, ny) in neighbours {
+            if nx < w && ny < h && mask.get_pixel(nx, ny)[0] != 0 && is_background(nx, ny) {
+                mask.put_pixel(nx, ny, Luma([0]));
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    mask
+}
+
+/// Coordinates of the outermost pixel ring
+fn border(w: u32, h: u32) -> impl Iterator<Item = (u32, u32)> {
+    let rows = (0..w).flat_map(move |x| [(x, 0), (x, h - 1)]);
+    let columns = (1..h.saturating_sub(1)).flat_map(move |y| [(0, y), (w - 1, y)]);
+    rows.chain(columns)
+}
+
+impl std::str::FromStr for FloodFill {
+    type Err = String;
+
+    /// Parse `auto[,TOLERANCE]` or `#RRGGBB[,TOLERANCE]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (color, tolerance) = match s.split_once(',') {
+            Some((color, tolerance)) => (color.trim(), Some(tolerance.trim())),
+            None => (s.trim(), None),
+        };
+
+        let mut fill = FloodFill::default();
+        if color != "auto" {
+            fill.color = Some(parse_color(color)?);
+        }
+        if let Some(tolerance) = tolerance {
+            fill.tolerance = tolerance
+                .parse::<f32>()
+                .map_err(|e| format!("invalid flood fill '{}': {}", s, e))?;
+        }
+        Ok(fill)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    const MAGENTA: [u8; 3] = [255, 0, 255];
+
+    /// Magenta sprite sheet: a dark 6x6 sprite with a magenta pixel inside it
+    fn sprite() -> RgbImage {
+        let mut img = RgbImage::from_pixel(10, 10, Rgb(MAGENTA));
+        for y in 2..8 {
+            for x in 2..8 {
+                img.put_pixel(x, y, Rgb([10, 10, 10]));
+            }
+        }
+        img.put_pixel(5, 5, Rgb(MAGENTA));
+        img
+    }
+
+    #[test]
+    fn detects_the_dominant_border_color() {
+        let mut img = sprite();
+        // Slight noise and a few odd pixels on the border do not change the vote
+        img.put_pixel(0, 0, Rgb([250, 3, 252]));
+        img.put_pixel(9, 0, Rgb([0, 0, 0]));
+        let color = dominant_border_color(&img);
+        assert!(color[0] >= 254 && color[1] <= 1 && color[2] >= 254);
+    }
+
+    #[test]
+    fn keeps_enclosed_background_colored_pixels() {
+        let mask = flood_fill_mask(&sprite(), &FloodFill::default());
+
+        assert_eq!(mask.get_pixel(0, 0)[0], 0);
+        assert_eq!(mask.get_pixel(9, 9)[0], 0);
+        assert_eq!(mask.get_pixel(3, 3)[0], 255);
+        assert_eq!(mask.get_pixel(5, 5)[0], 255);
+    }
+
+    #[test]
+    fn tolerance_limits_the_fill() {
+        let mut img = sprite();
+        img.put_pixel(1, 1, Rgb([200, 0, 200]));
+
+        let strict = flood_fill_mask(&img, &FloodFill::default());
+        assert_eq!(strict.get_pixel(1, 1)[0], 255);
+
+        let loose = FloodFill {
+            tolerance: 30.0,
+            ..FloodFill::default()
+        };
+        assert_eq!(flood_fill_mask(&img, &loose).get_pixel(1, 1)[0], 0);
+    }
+
+    #[test]
+    fn explicit_color_overrides_detection() {
+        let fill = FloodFill {
+            color: Some([0, 255, 0]),
+            ..FloodFill::default()
+        };
+        let mask = flood_fill_mask(&sprite(), &fill);
+        assert!(mask.pixels().all(|p| p[0] == 255));
+    }
+
+    #[test]
+    fn parses_flood_fill_specs() {
+        assert_eq!("auto".parse::<FloodFill>(), Ok(FloodFill::default()));
+        assert_eq!(
+            "#ff00ff, 8".parse::<FloodFill>(),
+            Ok(FloodFill {
+                color: Some(MAGENTA),
+                tolerance: 8.0,
+            })
+        );
+
+        assert!("auto,x".parse::<FloodFill>().is_err());
+        assert!("#ff00".parse::<FloodFill>().is_err());
+        assert!("magenta".parse::<FloodFill>().is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::rect::Rect;
+use image::RgbaImage;
+
+/// Placement of the cut out subject, computed from the result alpha
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubjectGeometry {
+    /// Tight bounding box of all pixels with non-zero alpha, `None` for an empty cutout
+    pub bbox: Option<Rect>,
+    /// Alpha-weighted center of mass as (x, y)
+    pub centroid: Option<(f32, f32)>,
+    /// Alpha-weighted area in pixels (a half transparent pixel counts as 0.5)
+    pub area: f32,
+    /// `area` relative to the whole image
+    pub coverage: f32,
+}
+
+pub fn subject_geometry(image: &RgbaImage) -> SubjectGeometry {
+    let (w, h) = image.dimensions();
+
+    let mut min_x = u32::MAX;
+    let mut min_y = u32::MAX;
+    let mut max_x = 0;
+    let mut max_y = 0;
+    let mut area = 0f64;
+    let mut sum_x = 0f64;
+    let mut sum_y = 0f64;
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let a = pixel[3];
+        if a == 0 {
+            continue;
+        }
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+
+        let weight = a as f64 / 255.0;
+        area += weight;
+        sum_x += weight * (x as f64 + 0.5);
+        sum_y += weight * (y as f64 + 0.5);
+    }
+
+    if area == 0.0 {
+        return SubjectGeometry {
+            bbox: None,
+            centroid: None,
+            area: 0.0,
+            coverage: 0.0,
+        };
+    }
+
+    let total = (w as f64 * h as f64).max(1.0);
+
+    SubjectGeometry {
+        bbox: Some(Rect::new(
+            min_x,
+            min_y,
+            max_x - min_x + 1,
+            max_y - min_y + 1,
+        )),
+        centroid: Some(((sum_x / area) as f32, (sum_y / area) as f32)),
+        area: area as f32,
+        coverage: (area / total) as f32,
+    }
+}
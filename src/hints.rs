@@ -0,0 +1,85 @@
+use crate::error::RembgError;
+use crate::rect::Rect;
+use image::GrayImage;
+
+/// What to do with the pixels covered by a hint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintAction {
+    /// Always keep as foreground
+    Keep,
+    /// Always remove as background
+    Remove,
+}
+
+/// Area covered by a hint
+#[derive(Debug, Clone)]
+pub enum HintRegion {
+    Rect(Rect),
+    /// Scribble mask of the image size, non-zero pixels are covered
+    Mask(GrayImage),
+}
+
+/// Manual correction applied to the probability mask before thresholding
+#[derive(Debug, Clone)]
+pub struct MaskHint {
+    pub action: HintAction,
+    pub region: HintRegion,
+}
+
+impl MaskHint {
+    pub fn keep(region: HintRegion) -> Self {
+        Self {
+            action: HintAction::Keep,
+            region,
+        }
+    }
+
+    pub fn remove(region: HintRegion) -> Self {
+        Self {
+            action: HintAction::Remove,
+            region,
+        }
+    }
+}
+
+/// Force covered pixels of the probability mask to 255 (keep) or 0 (remove).
+/// Hints are applied in order, so later hints win where they overlap.
+pub fn apply_hints(probability: &mut GrayImage, hints: &[MaskHint]) -> Result<(), RembgError> {
+    let (width, height) = probability.dimensions();
+
+    for hint in hints {
+        let value = match hint.action {
+            HintAction::Keep => 255,
+            HintAction::Remove => 0,
+        };
+
+        match &hint.region {
+            HintRegion::Rect(rect) => {
+                let Some(rect) = rect.clamp_to(width, height) else {
+                    continue;
+                };
+                for y in rect.y..rect.bottom() {
+                    for x in rect.x..rect.right() {
+                        probability.get_pixel_mut(x, y).0[0] = value;
+                    }
+                }
+            }
+            HintRegion::Mask(mask) => {
+                if mask.dimensions() != (width, height) {
+                    return Err(RembgError::InvalidInput(format!(
+                        "Hint mask size {:?} does not match image size {:?}",
+                        mask.dimensions(),
+                        (width, height)
+                    )));
+                }
+                for (dst, src) in probability.pixels_mut().zip(mask.pixels()) {
+                    if src.0[0] != 0 {
+                        dst.0[0] = value;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
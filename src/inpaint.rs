@@ -0,0 +1,241 @@
+use crate::error::RembgError;
+use image::{GrayImage, Rgb, RgbImage, RgbaImage};
+use std::collections::VecDeque;
+
+/// Number of smoothing passes over the filled region after the initial fill
+const DIFFUSION_ITERATIONS: usize = 30;
+
+/// Fill the pixels where `mask` is non-zero from the surrounding image.
+///
+/// Classical (non-neural) diffusion inpainting: the hole is first filled layer by layer
+/// from its border inwards, each pixel taking the average of its already known
+/// neighbours, then smoothed with a few Gauss–Seidel passes of the Laplace equation
+/// while the known pixels stay fixed. For a `rembg` result use [`inpaint_subject`].
+pub fn inpaint(image: &RgbImage, mask: &GrayImage) -> Result<RgbImage, RembgError> {
+    if mask.dimensions() != image.dimensions() {
+        return Err(RembgError::InvalidInput(format!(
+            "Inpaint mask size {:?} does not match image size {:?}",
+            mask.dimensions(),
+            image.dimensions()
+        )));
+    }
+
+    let hole: Vec<bool> = mask.pixels().map(|p| p[0] != 0).collect();
+    Ok(fill(image, &hole))
+}
+
+/// Erase the subject of a cutout such as `RemovalResult::image`: every pixel that is
+/// not fully transparent is filled from the transparent background around it.
+pub fn inpaint_subject(cutout: &RgbaImage) -> RgbImage {
+    let hole: Vec<bool> = cutout.pixels().map(|p| p[3] > 0).collect();
+    fill(&rgb(cutout), &hole)
+}
+
+/// Fill every pixel that is not fully opaque from the opaque pixels. `scene` is the
+/// original image with the mask as alpha, so no edge cleanup leaks into the fill.
+pub(crate) fn inpaint_transparent(scene: &RgbaImage) -> RgbImage {
+    let hole: Vec<bool> = scene.pixels().map(|p| p[3] < 255).collect();
+    fill(&rgb(scene), &hole)
+}
+
+fn rgb(cutout: &RgbaImage) -> RgbImage {
+    RgbImage::from_fn(cutout.width(), cutout.height(), |x, y| {
+        let p = cutout.get_pixel(x, y);
+        Rgb([p[0], p[1], p[2]])
+    })
+}
+
+/// Diffusion fill of the pixels flagged in `hole`, one flag per pixel in row order
+fn fill(image: &RgbImage, hole: &[bool]) -> RgbImage {
+    let (w, h) = image.dimensions();
+    let idx = |x: u32, y: u32| (y * w + x) as usize;
+
+    let mut colors: Vec<[f32; 3]> = image
+        .pixels()
+        .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+        .collect();
+    let mut known: Vec<bool> = hole.iter().map(|h| !h).collect();
+
+    if !known.iter().any(|k| *k) {
+        // Nothing to sample from
+        return image.clone();
+    }
+
+    // Breadth-first order from the hole border inwards
+    let mut order = Vec::new();
+    let mut queued = known.clone();
+    let mut queue = VecDeque::new();
+    for y in 0..h {
+        for x in 0..w {
+            if known[idx(x, y)] {
+                continue;
+            }
+            if neighbours(x, y, w, h).any(|(nx, ny, _)| known[idx(nx, ny)]) {
+                queued[idx(x, y)] = true;
+                queue.push_back((x, y));
+            }
+        }
+    }
+    while let Some((x, y)) = queue.pop_front() {
+        order.push((x, y));
+        for (nx, ny, _) in neighbours(x, y, w, h) {
+            let i = idx(nx, ny);
+            if !queued[i] {
+                queued[i] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    // Initial fill: average of known neighbours, diagonals weighted less
+    for &(x, y) in &order {
+        let mut sum = [0f32; 3];
+        let mut weight = 0f32;
+        for (nx, ny, wgt) in neighbours(x, y, w, h) {
+            let i = idx(nx, ny);
+            if known[i] {
+                for c in 0..3 {
+                    sum[c] += colors[i][c] * wgt;
+                }
+                weight += wgt;
+            }
+        }
+        let i = idx(x, y);
+        if weight > 0.0 {
+            colors[i] = sum.map(|s| s / weight);
+        }
+        known[i] = true;
+    }
+
+    // Diffusion: smooth out streaks of the layered fill
+    for _ in 0..DIFFUSION_ITERATIONS {
+        for &(x, y) in &order {
+            let mut sum = [0f32; 3];
+            let mut weight = 0f32;
+            for (nx, ny, wgt) in neighbours(x, y, w, h) {
+                let i = idx(nx, ny);
+                for c in 0..3 {
+                    sum[c] += colors[i][c] * wgt;
+                }
+                weight += wgt;
+            }
+            colors[idx(x, y)] = sum.map(|s| s / weight);
+        }
+    }
+
+    let mut out = image.clone();
+    for (x, y, pixel) in out.enumerate_pixels_mut() {
+        let i = idx(x, y);
+        if hole[i] {
+            let c = colors[i];
+            *pixel = Rgb(c.map(|v| v.round().clamp(0.0, 255.0) as u8));
+        }
+    }
+    out
+}
+
+/// 8-neighbourhood inside the image with distance weights
+fn neighbours(x: u32, y: u32, w: u32, h: u32) -> impl Iterator<Item = (u32, u32, f32)> {
+    const DIAGONAL: f32 = std::f32::consts::FRAC_1_SQRT_2;
+    const OFFSETS: [(i64, i64, f32); 8] = [
+        (-1, 0, 1.0),
+        (1, 0, 1.0),
+        (0, -1, 1.0),
+        (0, 1, 1.0),
+        (-1, -1, DIAGONAL),
+        (1, -1, DIAGONAL),
+        (-1, 1, DIAGONAL),
+        (1, 1, DIAGONAL),
+    ];
+    OFFSETS.into_iter().filter_map(move |(dx, dy, wgt)| {
+        let nx = x as i64 + dx;
+        let ny = y as i64 + dy;
+        if nx >= 0 && ny >= 0 && nx < w as i64 && ny < h as i64 {
+            Some((nx as u32, ny as u32, wgt))
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Luma, Rgba};
+
+    /// Horizontal ramp, 10 levels per column
+    fn ramp() -> RgbImage {
+        RgbImage::from_fn(21, 9, |x, _| Rgb([(x * 10) as u8, 50, 200 - (x * 5) as u8]))
+    }
+
+    fn block_mask(w: u32, h: u32) -> GrayImage {
+        GrayImage::from_fn(w, h, |x, y| {
+            Luma([if (8..13).contains(&x) && (3..6).contains(&y) {
+                255
+            } else {
+                0
+            }])
+        })
+    }
+
+    #[test]
+    fn fills_a_ramp_hole_and_keeps_known_pixels() {
+        let image = ramp();
+        let mask = block_mask(21, 9);
+        let out = inpaint(&image, &mask).unwrap();
+
+        for (x, y, p) in out.enumerate_pixels() {
+            let expected = image.get_pixel(x, y);
+            if mask.get_pixel(x, y)[0] == 0 {
+                assert_eq!(p, expected);
+            } else {
+                for c in 0..3 {
+                    assert!(p[c].abs_diff(expected[c]) <= 3, "{:?} vs {:?}", p, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn subject_is_filled_from_the_transparent_background() {
+        let mut cutout = RgbaImage::from_pixel(10, 10, Rgba([30, 120, 90, 0]));
+        for y in 3..7 {
+            for x in 3..7 {
+                cutout.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+            }
+        }
+        cutout.put_pixel(2, 5, Rgba([255, 0, 0, 40]));
+
+        let out = inpaint_subject(&cutout);
+        assert!(out.pixels().all(|p| *p == Rgb([30, 120, 90])));
+    }
+
+    #[test]
+    fn transparent_pixels_are_filled_from_the_opaque_ones() {
+        let mut scene = RgbaImage::from_pixel(6, 6, Rgba([10, 20, 30, 255]));
+        scene.put_pixel(2, 2, Rgba([255, 255, 255, 0]));
+        scene.put_pixel(3, 2, Rgba([255, 255, 255, 128]));
+
+        let out = inpaint_transparent(&scene);
+        assert!(out.pixels().all(|p| *p == Rgb([10, 20, 30])));
+    }
+
+    #[test]
+    fn empty_or_full_hole_returns_the_image() {
+        let image = ramp();
+        let none = GrayImage::new(21, 9);
+        assert_eq!(inpaint(&image, &none).unwrap(), image);
+
+        let all = GrayImage::from_pixel(21, 9, Luma([255]));
+        assert_eq!(inpaint(&image, &all).unwrap(), image);
+    }
+
+    #[test]
+    fn rejects_mismatched_mask() {
+        let mask = GrayImage::new(4, 4);
+        assert!(matches!(
+            inpaint(&ramp(), &mask),
+            Err(RembgError::InvalidInput(_))
+        ));
+    }
+}
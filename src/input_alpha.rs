@@ -0,0 +1,130 @@
+use crate::background::parse_color;
+use image::{DynamicImage, GrayImage, RgbImage};
+
+/// What to do with transparency that is already present in the input image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputAlpha {
+    /// Drop the input alpha, the predicted mask alone decides transparency
+    #[default]
+    Ignore,
+    /// Keep the input transparency by multiplying it with the predicted alpha
+    Multiply,
+    /// Composite the image onto this color before inference
+    Matte([u8; 3]),
+}
+
+/// Flatten a transparent image onto the matte color of the `Matte` policy.
+/// Other policies and opaque images are returned unchanged.
+pub fn matte_input(image: DynamicImage, policy: &InputAlpha) -> DynamicImage {
+    let InputAlpha::Matte(color) = policy else {
+        return image;
+    };
+    if !image.color().has_alpha() {
+        return image;
+    }
+
+    let rgba = image.to_rgba8();
+    let matted = RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let p = rgba.get_pixel(x, y);
+        let a = p[3] as u32;
+        image::Rgb(
+            [0, 1, 2].map(|c| ((p[c] as u32 * a + color[c] as u32 * (255 - a) + 127) / 255) as u8),
+        )
+    });
+    DynamicImage::ImageRgb8(matted)
+}
+
+/// Multiply `alpha` with the alpha channel of the input image
+pub(crate) fn multiply_input_alpha(alpha: &mut GrayImage, image: &DynamicImage) {
+    if !image.color().has_alpha() {
+        return;
+    }
+
+    let rgba = image.to_rgba8();
+    for (dst, src) in alpha.pixels_mut().zip(rgba.pixels()) {
+        dst.0[0] = ((dst.0[0] as u32 * src.0[3] as u32 + 127) / 255) as u8;
+    }
+}
+
+impl std::str::FromStr for InputAlpha {
+    type Err = String;
+
+    /// Parse `ignore`, `multiply` or `matte:#RRGGBB`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("matte", color)) => Ok(InputAlpha::Matte(parse_color(color)?)),
+            None if s == "ignore" => Ok(InputAlpha::Ignore),
+            None if s == "multiply" => Ok(InputAlpha::Multiply),
+            _ => Err(format!(
+                "invalid input alpha policy '{}': expected ignore, multiply or matte:#RRGGBB",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Luma, Rgb, Rgba, RgbaImage};
+
+    /// Opaque, half transparent and fully transparent pixel
+    fn transparent_input() -> DynamicImage {
+        let mut rgba = RgbaImage::new(3, 1);
+        rgba.put_pixel(0, 0, Rgba([10, 20, 30, 255]));
+        rgba.put_pixel(1, 0, Rgba([200, 100, 0, 128]));
+        rgba.put_pixel(2, 0, Rgba([90, 90, 90, 0]));
+        DynamicImage::ImageRgba8(rgba)
+    }
+
+    #[test]
+    fn matte_blends_onto_the_color() {
+        let matted = matte_input(transparent_input(), &InputAlpha::Matte([255, 255, 255]));
+
+        let rgb = matted.as_rgb8().unwrap();
+        assert_eq!(*rgb.get_pixel(0, 0), Rgb([10, 20, 30]));
+        assert_eq!(*rgb.get_pixel(1, 0), Rgb([227, 177, 127]));
+        assert_eq!(*rgb.get_pixel(2, 0), Rgb([255, 255, 255]));
+    }
+
+    #[test]
+    fn opaque_images_and_other_policies_pass_through() {
+        let opaque = DynamicImage::ImageRgb8(RgbImage::from_pixel(2, 2, Rgb([1, 2, 3])));
+        assert_eq!(
+            matte_input(opaque.clone(), &InputAlpha::Matte([0, 0, 0])),
+            opaque
+        );
+
+        for policy in [InputAlpha::Ignore, InputAlpha::Multiply] {
+            assert_eq!(
+                matte_input(transparent_input(), &policy),
+                transparent_input()
+            );
+        }
+    }
+
+    #[test]
+    fn multiplies_the_input_alpha() {
+        let mut alpha = GrayImage::from_pixel(3, 1, Luma([200]));
+        multiply_input_alpha(&mut alpha, &transparent_input());
+        assert_eq!(alpha.as_raw(), &[200, 100, 0]);
+
+        let mut alpha = GrayImage::from_pixel(2, 2, Luma([200]));
+        let opaque = DynamicImage::ImageRgb8(RgbImage::new(2, 2));
+        multiply_input_alpha(&mut alpha, &opaque);
+        assert!(alpha.pixels().all(|p| p[0] == 200));
+    }
+
+    #[test]
+    fn parses_policies() {
+        assert_eq!("ignore".parse(), Ok(InputAlpha::Ignore));
+        assert_eq!("multiply".parse(), Ok(InputAlpha::Multiply));
+        assert_eq!(
+            "matte:#FF8000".parse(),
+            Ok(InputAlpha::Matte([255, 128, 0]))
+        );
+        for s in ["", "keep", "matte", "matte:", "matte:#12345", "ignore:1"] {
+            assert!(s.parse::<InputAlpha>().is_err(), "{}", s);
+        }
+    }
+}
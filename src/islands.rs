@@ -0,0 +1,152 @@
+use image::{GrayImage, Luma};
+use imageproc::region_labelling::{Connectivity, connected_components};
+
+/// Drop foreground components (alpha > 0, 8-connected) smaller than `min_area` pixels.
+/// With `keep_largest` only the biggest component survives.
+pub fn remove_islands(alpha: &mut GrayImage, min_area: u32, keep_largest: bool) {
+    let (width, height) = alpha.dimensions();
+    let binary = GrayImage::from_fn(width, height, |x, y| {
+        Luma([if alpha.get_pixel(x, y)[0] > 0 { 255 } else { 0 }])
+    });
+    let labels = connected_components(&binary, Connectivity::Eight, Luma([0u8]));
+
+    let areas = component_areas(&labels);
+    let largest = areas
+        .iter()
+        .enumerate()
+        .skip(1)
+        .max_by_key(|(_, area)| **area)
+        .map(|(label, _)| label);
+
+    for (x, y, label) in labels.enumerate_pixels() {
+        let label = label[0] as usize;
+        if label == 0 {
+            continue;
+        }
+        let drop = areas[label] < min_area as u64 || (keep_largest && Some(label) != largest);
+        if drop {
+            alpha.put_pixel(x, y, Luma([0]));
+        }
+    }
+}
+
+/// Make enclosed background regions (alpha == 0, not touching the image border)
+/// of at most `max_area` pixels opaque.
+pub fn fill_holes(alpha: &mut GrayImage, max_area: u32) {
+    let (width, height) = alpha.dimensions();
+    let background = GrayImage::from_fn(width, height, |x, y| {
+        Luma([if alpha.get_pixel(x, y)[0] == 0 {
+            255
+        } else {
+            0
+        }])
+    });
+    // Background uses the dual connectivity of the 8-connected foreground
+    let labels = connected_components(&background, Connectivity::Four, Luma([0u8]));
+
+    let areas = component_areas(&labels);
+    let mut touches_border = vec![false; areas.len()];
+    for (x, y, label) in labels.enumerate_pixels() {
+        if x == 0 || y == 0 || x + 1 == width || y + 1 == height {
+            touches_border[label[0] as usize] = true;
+        }
+    }
+
+    for (x, y, label) in labels.enumerate_pixels() {
+        let label = label[0] as usize;
+        if label != 0 && !touches_border[label] && areas[label] <= max_area as u64 {
+            alpha.put_pixel(x, y, Luma([255]));
+        }
+    }
+}
+
+/// Pixel count per label, index 0 is the unlabelled background
+pub(crate) fn component_areas(labels: &image::ImageBuffer<Luma<u32>, Vec<u32>>) -> Vec<u64> {
+    let max_label = labels.pixels().map(|p| p[0]).max().unwrap_or(0) as usize;
+    let mut areas = vec![0u64; max_label + 1];
+    for label in labels.pixels() {
+        areas[label[0] as usize] += 1;
+    }
+    areas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill_rect(alpha: &mut GrayImage, x0: u32, y0: u32, w: u32, h: u32, value: u8) {
+        for y in y0..y0 + h {
+            for x in x0..x0 + w {
+                alpha.put_pixel(x, y, Luma([value]));
+            }
+        }
+    }
+
+    /// 12x12 mask: a 5x5 subject at (1,1), a 2x2 speck at (9,9) and a 1px diagonal pair
+    fn subject_with_specks() -> GrayImage {
+        let mut alpha = GrayImage::new(12, 12);
+        fill_rect(&mut alpha, 1, 1, 5, 5, 255);
+        fill_rect(&mut alpha, 9, 9, 2, 2, 255);
+        alpha.put_pixel(8, 1, Luma([100]));
+        alpha.put_pixel(9, 2, Luma([100]));
+        alpha
+    }
+
+    #[test]
+    fn removes_components_below_min_area() {
+        let mut alpha = subject_with_specks();
+        remove_islands(&mut alpha, 3, false);
+
+        // The diagonal pair is one 8-connected component of 2 pixels
+        assert_eq!(alpha.get_pixel(8, 1)[0], 0);
+        assert_eq!(alpha.get_pixel(9, 2)[0], 0);
+        assert_eq!(alpha.get_pixel(9, 9)[0], 255);
+        assert_eq!(alpha.get_pixel(3, 3)[0], 255);
+    }
+
+    #[test]
+    fn keeps_only_the_largest_component() {
+        let mut alpha = subject_with_specks();
+        remove_islands(&mut alpha, 0, true);
+
+        let opaque = alpha.pixels().filter(|p| p[0] > 0).count();
+        assert_eq!(opaque, 25);
+        assert_eq!(alpha.get_pixel(3, 3)[0], 255);
+    }
+
+    #[test]
+    fn fills_enclosed_holes_up_to_max_area() {
+        let mut alpha = GrayImage::new(12, 12);
+        fill_rect(&mut alpha, 1, 1, 10, 10, 255);
+        // 1px hole, 3x3 hole, and a notch open to the border
+        alpha.put_pixel(3, 3, Luma([0]));
+        fill_rect(&mut alpha, 6, 6, 3, 3, 0);
+        fill_rect(&mut alpha, 3, 7, 1, 4, 0);
+
+        let mut small = alpha.clone();
+        fill_holes(&mut small, 4);
+        assert_eq!(small.get_pixel(3, 3)[0], 255);
+        assert_eq!(small.get_pixel(7, 7)[0], 0);
+
+        let mut large = alpha.clone();
+        fill_holes(&mut large, 9);
+        assert_eq!(large.get_pixel(7, 7)[0], 255);
+        // Background touching the border is never filled, even when it is small
+        assert_eq!(large.get_pixel(0, 0)[0], 0);
+        assert_eq!(large.get_pixel(3, 7)[0], 0);
+        assert_eq!(large.get_pixel(3, 10)[0], 0);
+    }
+
+    #[test]
+    fn diagonal_contact_keeps_a_hole_closed() {
+        // The inner pixel touches the outside only diagonally, which does not
+        // connect background regions when the foreground is 8-connected
+        let mut alpha = GrayImage::new(5, 5);
+        fill_rect(&mut alpha, 1, 1, 3, 3, 255);
+        alpha.put_pixel(2, 2, Luma([0]));
+        alpha.put_pixel(3, 3, Luma([0]));
+
+        fill_holes(&mut alpha, 10);
+        assert_eq!(alpha.get_pixel(2, 2)[0], 255);
+    }
+}
@@ -1,10 +1,32 @@
 // CLI module is optional and compiled only when `cli` feature is enabled
 #[cfg(feature = "cli")]
 pub mod cli;
+pub mod alpha_curve;
+pub mod background;
+pub mod chroma_key;
+pub mod color_refine;
+pub mod confidence;
+pub mod defringe;
+pub mod despill;
 pub mod error;
+pub mod flood_fill;
+pub mod geometry;
+pub mod hints;
+pub mod inpaint;
+pub mod input_alpha;
+pub mod islands;
+pub mod load;
 pub mod manager;
+pub mod morphology;
 pub mod options;
+pub mod profile;
+pub mod prompt;
+pub mod rect;
 pub mod rembg;
 pub mod result;
+pub mod subjects;
+pub mod trimap;
+pub mod upsample;
 mod clean_sticker_border;
+pub mod smooth_contour;
 pub mod compress_png;
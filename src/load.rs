@@ -0,0 +1,43 @@
+use crate::error::RembgError;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use std::io::{BufRead, Cursor, Seek};
+use std::path::Path;
+
+/// Decode an image file, rotating and flipping it upright according to its EXIF
+/// orientation tag unless `apply_orientation` is false.
+pub fn load_image(path: &Path, apply_orientation: bool) -> Result<DynamicImage, RembgError> {
+    decode(
+        ImageReader::open(path)?.with_guessed_format()?,
+        apply_orientation,
+    )
+}
+
+/// Decode an image from memory, see [`load_image`]
+pub fn load_image_from_memory(
+    bytes: &[u8],
+    apply_orientation: bool,
+) -> Result<DynamicImage, RembgError> {
+    decode(
+        ImageReader::new(Cursor::new(bytes)).with_guessed_format()?,
+        apply_orientation,
+    )
+}
+
+fn decode<R: BufRead + Seek>(
+    reader: ImageReader<R>,
+    apply_orientation: bool,
+) -> Result<DynamicImage, RembgError> {
+    let mut decoder = reader.into_decoder()?;
+    // A missing or unreadable tag means the pixels are already upright
+    let orientation = if apply_orientation {
+        decoder.orientation().ok()
+    } else {
+        None
+    };
+    let mut image = DynamicImage::from_decoder(decoder)?;
+
+    if let Some(orientation) = orientation {
+        image.apply_orientation(orientation);
+    }
+    Ok(image)
+}
@@ -1,10 +1,25 @@
 use crate::error::RembgError;
-use ndarray::{Array, IxDyn};
+use crate::profile::ModelProfile;
+use ndarray::{Array, ArrayD, IxDyn};
 use ort::{Environment, GraphOptimizationLevel, Session, SessionBuilder};
 use std::path::Path;
+use std::sync::Arc;
 
 pub struct ModelManager {
     session: Session,
+    /// Mask decoder of a SAM-style encoder/decoder pair; `session` then holds the image encoder
+    decoder: Option<Session>,
+    profile: ModelProfile,
+}
+
+/// Inputs of a SAM-style mask decoder besides the image embeddings
+pub struct PromptTensors {
+    /// Point coordinates in encoder space, shape [1, N, 2]
+    pub point_coords: ArrayD<f32>,
+    /// Point labels, shape [1, N]
+    pub point_labels: ArrayD<f32>,
+    /// Original image size as [height, width]
+    pub orig_im_size: [f32; 2],
 }
 
 impl ModelManager {
@@ -13,30 +28,47 @@ impl ModelManager {
     /// Uses memory mapping - OS decides whether to keep model in RAM or load on demand.
     /// This is the most memory-efficient approach for long-running applications.
     pub fn from_file(model_path: &Path) -> Result<Self, RembgError> {
-        // Initialize ONNX Runtime environment
-        // Catch panic if dynamic library loading is not supported (e.g., musl)
-        let environment = match std::panic::catch_unwind(|| {
-            Environment::builder()
-                .with_name("rembg-rs")
-                .with_log_level(ort::LoggingLevel::Warning)
-                .build()
-        }) {
-            Ok(Ok(env)) => env.into_arc(),
-            Ok(Err(e)) => return Err(e.into()),
-            Err(_) => {
-                return Err(RembgError::OnnxRuntimeNotAvailable(
-                    "Dynamic loading not supported on this platform. Install libonnxruntime.so to /usr/local/lib/".to_string()
-                ));
-            }
-        };
+        Self::from_file_with_profile(model_path, ModelProfile::default())
+    }
 
-        // Create session with model file (uses memory mapping)
-        let session = SessionBuilder::new(&environment)?
-            .with_optimization_level(GraphOptimizationLevel::Level3)?
-            .with_intra_threads(4)?
-            .with_model_from_file(model_path)?;
+    /// Create a new model manager from model file with explicit input/output conventions
+    pub fn from_file_with_profile(
+        model_path: &Path,
+        profile: ModelProfile,
+    ) -> Result<Self, RembgError> {
+        let environment = create_environment()?;
+        let session = create_session(&environment, model_path)?;
 
-        Ok(Self { session })
+        Ok(Self {
+            session,
+            decoder: None,
+            profile,
+        })
+    }
+
+    /// Create a model manager for a SAM-style encoder/decoder model pair
+    ///
+    /// Such a manager is used with prompted segmentation, see [`crate::rembg::rembg_prompted`].
+    pub fn from_sam_files(encoder_path: &Path, decoder_path: &Path) -> Result<Self, RembgError> {
+        let environment = create_environment()?;
+        let session = create_session(&environment, encoder_path)?;
+        let decoder = create_session(&environment, decoder_path)?;
+
+        Ok(Self {
+            session,
+            decoder: Some(decoder),
+            profile: ModelProfile::sam(),
+        })
+    }
+
+    /// Input and output conventions of the loaded model
+    pub fn profile(&self) -> &ModelProfile {
+        &self.profile
+    }
+
+    /// True if the manager holds a prompted encoder/decoder pair
+    pub fn is_prompted(&self) -> bool {
+        self.decoder.is_some()
     }
 
     /// Run inference on preprocessed input
@@ -44,50 +76,157 @@ impl ModelManager {
         &self,
         input: &ndarray::Array4<f32>,
     ) -> Result<ndarray::Array4<f32>, RembgError> {
-        // Convert to dynamic dimensions with CowArray
-        let input_shape: Vec<usize> = input.shape().to_vec();
-        let input_data: Vec<f32> = input.iter().copied().collect();
-        let input_array = Array::from_shape_vec(IxDyn(&input_shape), input_data)
-            .map_err(|e| RembgError::TensorError(format!("Failed to create input array: {}", e)))?;
-
-        // Create CowArray for ORT
-        let input_cow = ndarray::CowArray::from(input_array.view());
-
-        // Create input tensor
-        let input_tensor = ort::Value::from_array(self.session.allocator(), &input_cow)?;
-
-        // Run inference
-        let outputs = self.session.run(vec![input_tensor])?;
-
-        // Extract output tensor
-        let output = outputs
-            .get(0)
-            .ok_or_else(|| RembgError::TensorError("No output from model".to_string()))?;
-
-        // Convert back to ndarray
-        let output_array = output.try_extract::<f32>()?.view().to_owned();
-
-        // Reshape to 4D if needed
-        let output_shape = output_array.shape();
-        let output_4d = if output_shape.len() == 4 {
-            output_array.into_dimensionality()?
-        } else if output_shape.len() == 3 {
-            output_array
-                .insert_axis(ndarray::Axis(0))
-                .into_dimensionality()?
-        } else if output_shape.len() == 2 {
-            // Add batch and channel dimensions
-            output_array
-                .insert_axis(ndarray::Axis(0))
-                .insert_axis(ndarray::Axis(0))
-                .into_dimensionality()?
-        } else {
-            return Err(RembgError::TensorError(format!(
-                "Unexpected output shape: {:?}",
-                output_shape
-            )));
-        };
-
-        Ok(output_4d)
+        let output_array = run_single(&self.session, input)?;
+        into_4d(output_array)
+    }
+
+    /// Run the encoder once on a preprocessed image and the decoder once per prompt
+    ///
+    /// Returns one array of mask logits with shape [1, C, H, W] per prompt, in the
+    /// order of `prompts`, at the `orig_im_size` of each prompt.
+    pub fn run_prompted_inference(
+        &self,
+        input: &ndarray::Array4<f32>,
+        prompts: &[PromptTensors],
+    ) -> Result<Vec<ndarray::Array4<f32>>, RembgError> {
+        let decoder = self.decoder.as_ref().ok_or_else(|| {
+            RembgError::InvalidInput("Model manager has no prompt decoder".to_string())
+        })?;
+
+        let embeddings = run_single(&self.session, input)?;
+
+        // No previous low resolution mask is fed back into the decoder
+        let mask_input = ArrayD::<f32>::zeros(IxDyn(&[1, 1, 256, 256]));
+        let has_mask_input = ArrayD::<f32>::zeros(IxDyn(&[1]));
+
+        let mut masks = Vec::with_capacity(prompts.len());
+        for prompt in prompts {
+            let size = Array::from_shape_vec(IxDyn(&[2]), prompt.orig_im_size.to_vec())?;
+
+            let embeddings_cow = ndarray::CowArray::from(embeddings.view());
+            let mask_input_cow = ndarray::CowArray::from(mask_input.view());
+            let has_mask_input_cow = ndarray::CowArray::from(has_mask_input.view());
+            let coords_cow = ndarray::CowArray::from(prompt.point_coords.view());
+            let labels_cow = ndarray::CowArray::from(prompt.point_labels.view());
+            let size_cow = ndarray::CowArray::from(size.view());
+
+            // Decoder exports differ in input order, so match tensors by name
+            let mut inputs = Vec::with_capacity(decoder.inputs.len());
+            for input in &decoder.inputs {
+                let array = match input.name.as_str() {
+                    "image_embeddings" => &embeddings_cow,
+                    "point_coords" => &coords_cow,
+                    "point_labels" => &labels_cow,
+                    "mask_input" => &mask_input_cow,
+                    "has_mask_input" => &has_mask_input_cow,
+                    "orig_im_size" => &size_cow,
+                    other => {
+                        return Err(RembgError::TensorError(format!(
+                            "Unexpected decoder input: {}",
+                            other
+                        )));
+                    }
+                };
+                inputs.push(ort::Value::from_array(decoder.allocator(), array)?);
+            }
+
+            // Outputs are ordered differently too, the masks are the `masks` output
+            let outputs = decoder.run(inputs)?;
+            let output = decoder
+                .outputs
+                .iter()
+                .position(|output| output.name == "masks")
+                .and_then(|index| outputs.get(index))
+                .ok_or_else(|| {
+                    RembgError::TensorError("Decoder has no `masks` output".to_string())
+                })?;
+            let output_array = output.try_extract::<f32>()?.view().to_owned();
+            masks.push(into_4d(output_array)?);
+        }
+
+        Ok(masks)
     }
 }
+
+fn create_environment() -> Result<Arc<Environment>, RembgError> {
+    // Initialize ONNX Runtime environment
+    // Catch panic if dynamic library loading is not supported (e.g., musl)
+    match std::panic::catch_unwind(|| {
+        Environment::builder()
+            .with_name("rembg-rs")
+            .with_log_level(ort::LoggingLevel::Warning)
+            .build()
+    }) {
+        Ok(Ok(env)) => Ok(env.into_arc()),
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => Err(RembgError::OnnxRuntimeNotAvailable(
+            "Dynamic loading not supported on this platform. Install libonnxruntime.so to /usr/local/lib/".to_string()
+        )),
+    }
+}
+
+fn create_session(
+    environment: &Arc<Environment>,
+    model_path: &Path,
+) -> Result<Session, RembgError> {
+    // Create session with model file (uses memory mapping)
+    let session = SessionBuilder::new(environment)?
+        .with_optimization_level(GraphOptimizationLevel::Level3)?
+        .with_intra_threads(4)?
+        .with_model_from_file(model_path)?;
+
+    Ok(session)
+}
+
+/// Run a single-input session and return its first output
+fn run_single(session: &Session, input: &ndarray::Array4<f32>) -> Result<ArrayD<f32>, RembgError> {
+    // Convert to dynamic dimensions with CowArray
+    let input_shape: Vec<usize> = input.shape().to_vec();
+    let input_data: Vec<f32> = input.iter().copied().collect();
+    let input_array = Array::from_shape_vec(IxDyn(&input_shape), input_data)
+        .map_err(|e| RembgError::TensorError(format!("Failed to create input array: {}", e)))?;
+
+    // Create CowArray for ORT
+    let input_cow = ndarray::CowArray::from(input_array.view());
+
+    // Create input tensor
+    let input_tensor = ort::Value::from_array(session.allocator(), &input_cow)?;
+
+    // Run inference
+    let outputs = session.run(vec![input_tensor])?;
+
+    // Extract output tensor
+    let output = outputs
+        .first()
+        .ok_or_else(|| RembgError::TensorError("No output from model".to_string()))?;
+
+    // Convert back to ndarray
+    let output_array = output.try_extract::<f32>()?.view().to_owned();
+
+    Ok(output_array)
+}
+
+/// Reshape model output to [batch, channel, height, width]
+fn into_4d(output_array: ArrayD<f32>) -> Result<ndarray::Array4<f32>, RembgError> {
+    let output_shape = output_array.shape();
+    let output_4d = if output_shape.len() == 4 {
+        output_array.into_dimensionality()?
+    } else if output_shape.len() == 3 {
+        output_array
+            .insert_axis(ndarray::Axis(0))
+            .into_dimensionality()?
+    } else if output_shape.len() == 2 {
+        // Add batch and channel dimensions
+        output_array
+            .insert_axis(ndarray::Axis(0))
+            .insert_axis(ndarray::Axis(0))
+            .into_dimensionality()?
+    } else {
+        return Err(RembgError::TensorError(format!(
+            "Unexpected output shape: {:?}",
+            output_shape
+        )));
+    };
+
+    Ok(output_4d)
+}
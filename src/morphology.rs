@@ -0,0 +1,207 @@
+use image::GrayImage;
+use imageproc::morphology::{
+    Mask, grayscale_close, grayscale_dilate, grayscale_erode, grayscale_open,
+};
+
+/// Morphological operation on the alpha mask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MorphOperation {
+    /// Shrink the foreground
+    Erode,
+    /// Grow the foreground
+    Dilate,
+    /// Erode then dilate: removes specks and thin protrusions
+    Open,
+    /// Dilate then erode: fills small gaps and notches
+    Close,
+}
+
+/// Structuring element shape
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KernelShape {
+    Square,
+    Diamond,
+    #[default]
+    Disk,
+}
+
+/// Single morphology step applied to the thresholded alpha
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MorphologyOp {
+    pub operation: MorphOperation,
+    /// Kernel radius in pixels
+    pub radius: u8,
+    pub shape: KernelShape,
+}
+
+/// Apply the operations in order. Works on soft alpha as well (grayscale min/max filters).
+pub fn apply_morphology(alpha: &GrayImage, ops: &[MorphologyOp]) -> GrayImage {
+    let mut out = alpha.clone();
+
+    for op in ops {
+        if op.radius == 0 {
+            continue;
+        }
+
+        let mask = match op.shape {
+            KernelShape::Square => Mask::square(op.radius),
+            KernelShape::Diamond => Mask::diamond(op.radius),
+            KernelShape::Disk => Mask::disk(op.radius),
+        };
+
+        out = match op.operation {
+            MorphOperation::Erode => grayscale_erode(&out, &mask),
+            MorphOperation::Dilate => grayscale_dilate(&out, &mask),
+            MorphOperation::Open => grayscale_open(&out, &mask),
+            MorphOperation::Close => grayscale_close(&out, &mask),
+        };
+    }
+
+    out
+}
+
+impl std::str::FromStr for MorphologyOp {
+    type Err = String;
+
+    /// Parse `OPERATION:RADIUS[:SHAPE]`, e.g. `open:2` or `close:3:square`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').map(str::trim).collect();
+
+        let (operation, radius, shape) = match parts.as_slice() {
+            [op, radius] => (*op, *radius, None),
+            [op, radius, shape] => (*op, *radius, Some(*shape)),
+            _ => {
+                return Err(format!(
+                    "invalid morphology '{}': expected OPERATION:RADIUS[:SHAPE]",
+                    s
+                ));
+            }
+        };
+
+        let operation = match operation {
+            "erode" => MorphOperation::Erode,
+            "dilate" => MorphOperation::Dilate,
+            "open" => MorphOperation::Open,
+            "close" => MorphOperation::Close,
+            other => {
+                return Err(format!(
+                    "invalid morphology operation '{}': expected erode, dilate, open or close",
+                    other
+                ));
+            }
+        };
+
+        let radius = radius
+            .parse::<u8>()
+            .map_err(|e| format!("invalid morphology radius '{}': {}", radius, e))?;
+
+        let shape = match shape {
+            None => KernelShape::default(),
+            Some("square") => KernelShape::Square,
+            Some("diamond") => KernelShape::Diamond,
+            Some("disk") => KernelShape::Disk,
+            Some(other) => {
+                return Err(format!(
+                    "invalid kernel shape '{}': expected square, diamond or disk",
+                    other
+                ));
+            }
+        };
+
+        Ok(MorphologyOp {
+            operation,
+            radius,
+            shape,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    fn op(operation: MorphOperation, radius: u8) -> MorphologyOp {
+        MorphologyOp {
+            operation,
+            radius,
+            shape: KernelShape::Square,
+        }
+    }
+
+    /// 9x9 mask with a 3x3 opaque block in the middle and a single speck in a corner
+    fn block_with_speck() -> GrayImage {
+        let mut alpha = GrayImage::new(9, 9);
+        for y in 3..6 {
+            for x in 3..6 {
+                alpha.put_pixel(x, y, Luma([255]));
+            }
+        }
+        alpha.put_pixel(0, 0, Luma([255]));
+        alpha
+    }
+
+    fn opaque(alpha: &GrayImage) -> usize {
+        alpha.pixels().filter(|p| p[0] == 255).count()
+    }
+
+    #[test]
+    fn erode_and_dilate_shrink_and_grow() {
+        let alpha = block_with_speck();
+
+        let eroded = apply_morphology(&alpha, &[op(MorphOperation::Erode, 1)]);
+        assert_eq!(opaque(&eroded), 1);
+        assert_eq!(eroded.get_pixel(4, 4)[0], 255);
+
+        let dilated = apply_morphology(&alpha, &[op(MorphOperation::Dilate, 1)]);
+        assert_eq!(dilated.get_pixel(2, 2)[0], 255);
+        assert_eq!(dilated.get_pixel(1, 1)[0], 255);
+        assert_eq!(dilated.get_pixel(7, 7)[0], 0);
+    }
+
+    #[test]
+    fn open_removes_specks_and_keeps_blocks() {
+        let opened = apply_morphology(&block_with_speck(), &[op(MorphOperation::Open, 1)]);
+        assert_eq!(opened.get_pixel(0, 0)[0], 0);
+        assert_eq!(opaque(&opened), 9);
+    }
+
+    #[test]
+    fn close_fills_small_gaps() {
+        let mut alpha = GrayImage::from_pixel(7, 7, Luma([255]));
+        alpha.put_pixel(3, 3, Luma([0]));
+        let closed = apply_morphology(&alpha, &[op(MorphOperation::Close, 1)]);
+        assert_eq!(closed.get_pixel(3, 3)[0], 255);
+    }
+
+    #[test]
+    fn zero_radius_is_a_no_op() {
+        let alpha = block_with_speck();
+        assert_eq!(
+            apply_morphology(&alpha, &[op(MorphOperation::Erode, 0)]),
+            alpha
+        );
+    }
+
+    #[test]
+    fn parses_morphology_specs() {
+        assert_eq!(
+            "open:2".parse::<MorphologyOp>(),
+            Ok(MorphologyOp {
+                operation: MorphOperation::Open,
+                radius: 2,
+                shape: KernelShape::Disk,
+            })
+        );
+        assert_eq!(
+            "close:3:square".parse::<MorphologyOp>(),
+            Ok(op(MorphOperation::Close, 3))
+        );
+
+        assert!("open".parse::<MorphologyOp>().is_err());
+        assert!("grow:2".parse::<MorphologyOp>().is_err());
+        assert!("open:-1".parse::<MorphologyOp>().is_err());
+        assert!("open:2:star".parse::<MorphologyOp>().is_err());
+        assert!("open:2:disk:1".parse::<MorphologyOp>().is_err());
+    }
+}
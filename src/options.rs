@@ -1,3 +1,12 @@
+use crate::alpha_curve::AlphaCurve;
+use crate::background::Background;
+use crate::color_refine::ColorRefine;
+use crate::despill::Despill;
+use crate::hints::MaskHint;
+use crate::input_alpha::InputAlpha;
+use crate::morphology::MorphologyOp;
+use crate::rect::Rect;
+use crate::upsample::MaskUpsampling;
 use derive_builder::Builder;
 
 /// Options for background removal
@@ -11,19 +20,98 @@ pub struct RemovalOptions {
     /// - 153–179: Stronger cutout, cleaner edges (≈0.6–0.7)
     pub threshold: u8,
 
+    /// Pick the threshold per image from the probability histogram (Otsu).
+    /// `threshold` is used as a fallback when the mask has a single level.
+    pub auto_threshold: bool,
+
     /// If true, creates hard cutout without semi-transparency.
     /// If false, allows soft edges for more natural blending.
     pub binary: bool,
 
+    /// Smooth and anti-alias the outline of a binary cutout.
+    /// Gaussian sigma in pixels, 0 disables smoothing. Only used when `binary` is true.
+    pub contour_smoothing: f32,
+
+    /// Probability to alpha transfer function used when `binary` is false.
+    pub alpha_curve: AlphaCurve,
+
     pub sticker: bool,
+
+    /// Keep the background and make the subject transparent.
+    /// Applied after all mask clean-up, so it shares thresholding and morphology.
+    /// The trimap and the confidence coverage and empty/full flags are inverted with it.
+    pub invert: bool,
+
+    /// Erosion/dilation/opening/closing steps applied in order to the thresholded alpha.
+    pub morphology: Vec<MorphologyOp>,
+
+    /// Foreground components smaller than this many pixels are removed (0 = keep all).
+    pub min_island_area: u32,
+
+    /// Keep only the largest foreground component.
+    pub keep_largest_island: bool,
+
+    /// Enclosed transparent holes up to this many pixels are filled (0 = keep holes).
+    pub max_hole_area: u32,
+
+    /// Produce a trimap with an unknown band of this many pixels around the outline.
+    pub trimap_band: Option<u8>,
+
+    /// Also return one cropped cutout per connected object.
+    pub split_subjects: bool,
+
+    /// Composite the cutout onto this background into an opaque RGB image.
+    pub background: Option<Background>,
+
+    /// Manual keep/remove regions applied to the probability mask before thresholding.
+    pub hints: Vec<MaskHint>,
+
+    /// Region of interest. The model runs on this crop only and everything
+    /// outside it is transparent in the full-size result.
+    pub roi: Option<Rect>,
+
+    /// How the model mask is scaled back to the image size.
+    pub upsampling: MaskUpsampling,
+
+    /// Refine the mask near its outline by color distance to a near-uniform
+    /// background detected on the image border.
+    pub color_refine: Option<ColorRefine>,
+
+    /// Remove key color spill from the RGB of edge pixels in the cutout.
+    pub despill: Option<Despill>,
+
+    /// Recolor pixels within this many pixels of the alpha edge from the solid
+    /// interior, removing rims of the old background (0 = off).
+    pub defringe: u8,
+
+    /// Handling of transparency already present in the input image.
+    pub input_alpha: InputAlpha,
 }
 
 impl Default for RemovalOptions {
     fn default() -> Self {
         Self {
             threshold: 160,
+            auto_threshold: false,
             binary: false,
+            contour_smoothing: 0.0,
+            alpha_curve: AlphaCurve::Linear,
             sticker: false,
+            invert: false,
+            morphology: Vec::new(),
+            min_island_area: 0,
+            keep_largest_island: false,
+            max_hole_area: 0,
+            trimap_band: None,
+            split_subjects: false,
+            background: None,
+            hints: Vec::new(),
+            roi: None,
+            upsampling: MaskUpsampling::Lanczos3,
+            color_refine: None,
+            despill: None,
+            defringe: 0,
+            input_alpha: InputAlpha::Ignore,
         }
     }
 }
@@ -0,0 +1,78 @@
+/// How raw model outputs are turned into probabilities
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum OutputNormalization {
+    /// Treat outputs as logits and apply the logistic function
+    Sigmoid,
+    /// Stretch outputs to [0, 1] by their minimum and maximum, as python rembg does
+    MinMax,
+    /// Outputs already are probabilities in [0, 1]
+    Identity,
+}
+
+/// How 8-bit input pixels are brought to [0, 1] before mean/std normalization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum InputScaling {
+    /// Divide by 255
+    #[default]
+    Fixed,
+    /// Divide by the brightest channel value of the resized image, as python rembg does
+    ImageMax,
+}
+
+/// Input and output conventions of a salient object model
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelProfile {
+    /// Square input resolution of the model
+    pub input_size: u32,
+    /// How pixels are scaled to [0, 1]
+    pub scaling: InputScaling,
+    /// Per-channel mean of the [0, 1] input
+    pub mean: [f32; 3],
+    /// Per-channel standard deviation of the [0, 1] input
+    pub std: [f32; 3],
+    pub normalization: OutputNormalization,
+}
+
+impl ModelProfile {
+    /// U2-Net family (u2net, u2netp, u2net_human_seg, silueta) as python rembg runs it
+    pub fn u2net() -> Self {
+        Self {
+            scaling: InputScaling::ImageMax,
+            normalization: OutputNormalization::MinMax,
+            ..Self::default()
+        }
+    }
+
+    /// SAM image encoders: longest side scaled to 1024 and padded, decoders output logits
+    pub fn sam() -> Self {
+        Self {
+            input_size: 1024,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_scaling(mut self, scaling: InputScaling) -> Self {
+        self.scaling = scaling;
+        self
+    }
+
+    pub fn with_normalization(mut self, normalization: OutputNormalization) -> Self {
+        self.normalization = normalization;
+        self
+    }
+}
+
+impl Default for ModelProfile {
+    /// U2-Net input conventions with sigmoid output, the behaviour thresholds were tuned for
+    fn default() -> Self {
+        Self {
+            input_size: 320,
+            scaling: InputScaling::Fixed,
+            mean: [0.485, 0.456, 0.406],
+            std: [0.229, 0.224, 0.225],
+            normalization: OutputNormalization::Sigmoid,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::rect::Rect;
+
+/// Role of a prompt point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointLabel {
+    /// Pixel belongs to the subject
+    Foreground,
+    /// Pixel belongs to the background
+    Background,
+}
+
+/// Single click hint in original image coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PromptPoint {
+    pub x: f32,
+    pub y: f32,
+    pub label: PointLabel,
+}
+
+/// Hints for prompted (SAM-style) segmentation.
+///
+/// Every box is decoded together with all points and the resulting masks are merged,
+/// so several boxes select several subjects.
+#[derive(Debug, Clone, Default)]
+pub struct Prompt {
+    pub points: Vec<PromptPoint>,
+    pub boxes: Vec<Rect>,
+}
+
+impl Prompt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn foreground(mut self, x: f32, y: f32) -> Self {
+        self.points.push(PromptPoint {
+            x,
+            y,
+            label: PointLabel::Foreground,
+        });
+        self
+    }
+
+    pub fn background(mut self, x: f32, y: f32) -> Self {
+        self.points.push(PromptPoint {
+            x,
+            y,
+            label: PointLabel::Background,
+        });
+        self
+    }
+
+    pub fn with_box(mut self, rect: Rect) -> Self {
+        self.boxes.push(rect);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty() && self.boxes.is_empty()
+    }
+}
@@ -0,0 +1,68 @@
+/// Axis-aligned rectangle in image pixel coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Exclusive right edge
+    pub fn right(&self) -> u32 {
+        self.x.saturating_add(self.width)
+    }
+
+    /// Exclusive bottom edge
+    pub fn bottom(&self) -> u32 {
+        self.y.saturating_add(self.height)
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    /// Clip the rectangle to an image of the given size.
+    /// Returns `None` if nothing is left after clipping.
+    pub fn clamp_to(&self, width: u32, height: u32) -> Option<Rect> {
+        let x0 = self.x.min(width);
+        let y0 = self.y.min(height);
+        let x1 = self.right().min(width);
+        let y1 = self.bottom().min(height);
+        if x1 > x0 && y1 > y0 {
+            Some(Rect::new(x0, y0, x1 - x0, y1 - y0))
+        } else {
+            None
+        }
+    }
+}
+
+impl std::str::FromStr for Rect {
+    type Err = String;
+
+    /// Parse `x,y,width,height`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<u32> = s
+            .split(',')
+            .map(|p| p.trim().parse::<u32>())
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("invalid rectangle '{}': {}", s, e))?;
+
+        match parts.as_slice() {
+            [x, y, width, height] => Ok(Rect::new(*x, *y, *width, *height)),
+            _ => Err(format!(
+                "invalid rectangle '{}': expected x,y,width,height",
+                s
+            )),
+        }
+    }
+}
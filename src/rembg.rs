@@ -1,153 +1,426 @@
+use crate::alpha_curve::AlphaCurve;
+use crate::background::replace_background;
+use crate::chroma_key::{ChromaKey, chroma_key_mask};
 use crate::clean_sticker_border::clean_sticker_border;
+use crate::color_refine::refine_with_background_color;
+use crate::confidence::{inverted_mask_confidence, mask_confidence, uncertainty_map};
+use crate::defringe::defringe;
+use crate::despill::despill;
 use crate::error::RembgError;
-use crate::manager::ModelManager;
+use crate::flood_fill::{FloodFill, flood_fill_mask};
+use crate::geometry::subject_geometry;
+use crate::hints::apply_hints;
+use crate::input_alpha::{InputAlpha, matte_input, multiply_input_alpha};
+use crate::islands::{fill_holes, remove_islands};
+use crate::manager::{ModelManager, PromptTensors};
+use crate::morphology::apply_morphology;
 use crate::options::RemovalOptions;
+use crate::profile::{InputScaling, ModelProfile, OutputNormalization};
+use crate::prompt::{PointLabel, Prompt};
+use crate::rect::Rect;
 use crate::result::RemovalResult;
-use image::{DynamicImage, GenericImageView, ImageBuffer, Luma, RgbImage, Rgba, RgbaImage};
-use ndarray::{Array4, Axis};
+use crate::smooth_contour::smooth_contour;
+use crate::subjects::split_subjects;
+use crate::trimap::{invert_trimap, trimap};
+use crate::upsample::{MaskUpsampling, joint_bilateral_upsample};
+use image::{DynamicImage, GenericImageView, GrayImage, Luma, RgbImage, Rgba, RgbaImage};
+use imageproc::contrast::otsu_level;
+use ndarray::{Array, Array4, ArrayView2, Axis, IxDyn};
 
 pub fn rembg(
     manager: &ModelManager,
     image: DynamicImage,
     options: &RemovalOptions,
 ) -> Result<RemovalResult, RembgError> {
+    if manager.is_prompted() {
+        return Err(RembgError::InvalidInput(
+            "Encoder/decoder models need a prompt, use rembg_prompted".to_string(),
+        ));
+    }
+
+    let image = matte_input(image, &options.input_alpha);
+    let (original_width, original_height) = image.dimensions();
+
+    // The model only sees the region of interest, at full model resolution
+    let region = match options.roi {
+        Some(roi) => roi
+            .clamp_to(original_width, original_height)
+            .ok_or_else(|| {
+                RembgError::InvalidInput(format!("ROI {:?} is outside the image", roi))
+            })?,
+        None => Rect::new(0, 0, original_width, original_height),
+    };
+
+    // Convert to RGB if not already
+    let mut rgb_img = image.to_rgb8();
+    if options.roi.is_some() {
+        rgb_img =
+            image::imageops::crop_imm(&rgb_img, region.x, region.y, region.width, region.height)
+                .to_image();
+    }
+
+    let preprocessed = model_input(&rgb_img, manager.profile());
+
+    // Run model inference
+    let mask_output: Array4<f32> = manager.run_inference(&preprocessed)?;
+
+    if mask_output.ndim() != 4 {
+        return Err(RembgError::PreprocessingError(format!(
+            "Unexpected mask shape: {:?}",
+            mask_output.shape()
+        )));
+    }
+
+    // Probability mask at original resolution
+    let probability = {
+        let temp_axis = mask_output.index_axis(Axis(0), 0);
+        let mask_data = temp_axis.index_axis(Axis(0), 0);
+        let mut mask_gray = probability_mask(mask_data, manager.profile().normalization);
+
+        if mask_gray.dimensions() != (region.width, region.height) {
+            mask_gray = match options.upsampling {
+                MaskUpsampling::Lanczos3 => image::imageops::resize(
+                    &mask_gray,
+                    region.width,
+                    region.height,
+                    image::imageops::FilterType::Lanczos3,
+                ),
+                MaskUpsampling::JointBilateral {
+                    spatial_sigma,
+                    range_sigma,
+                } => joint_bilateral_upsample(&mask_gray, &rgb_img, spatial_sigma, range_sigma),
+            };
+        }
+
+        if mask_gray.dimensions() != (original_width, original_height) {
+            // Everything outside the region of interest is background
+            let mut full = GrayImage::new(original_width, original_height);
+            image::imageops::replace(&mut full, &mask_gray, region.x as i64, region.y as i64);
+            full
+        } else {
+            mask_gray
+        }
+    };
+
+    apply_mask(image, probability, options)
+}
+
+/// Remove background with a SAM-style encoder/decoder pair guided by point and box hints
+///
+/// The manager must be created with [`ModelManager::from_sam_files`].
+/// The decoder already returns masks at the image size, so `options.upsampling` has no
+/// effect, and the encoder always sees the whole image: `options.roi` only clears the
+/// mask outside the region instead of cropping the input.
+pub fn rembg_prompted(
+    manager: &ModelManager,
+    image: DynamicImage,
+    prompt: &Prompt,
+    options: &RemovalOptions,
+) -> Result<RemovalResult, RembgError> {
+    if prompt.is_empty() {
+        return Err(RembgError::InvalidInput(
+            "Prompt needs at least one point or box".to_string(),
+        ));
+    }
+
+    let image = matte_input(image, &options.input_alpha);
     let (original_width, original_height) = image.dimensions();
 
+    // SAM encoders take the image with its longest side scaled to the input size, padded to a square
+    let profile = manager.profile();
+    let target_size = profile.input_size;
+    let scale = target_size as f32 / original_width.max(original_height) as f32;
+
     let preprocessed = {
-        // Convert to RGB if not already
         let rgb_img = image.to_rgb8();
-
-        // Resize image
-        let target_width = 320;
-        let target_height = 320;
+        let new_width = ((original_width as f32 * scale).round() as u32).clamp(1, target_size);
+        let new_height = ((original_height as f32 * scale).round() as u32).clamp(1, target_size);
         let resized = image::imageops::resize(
             &rgb_img,
-            target_width,
-            target_height,
-            image::imageops::FilterType::Lanczos3,
+            new_width,
+            new_height,
+            image::imageops::FilterType::Triangle,
         );
 
-        // Convert to normalized float array with shape [1, 3, height, width]
-        let mut array = Array4::<f32>::zeros((1, 3, target_height as usize, target_width as usize));
+        let mut array = Array4::<f32>::zeros((1, 3, target_size as usize, target_size as usize));
 
         for (x, y, pixel) in resized.enumerate_pixels() {
-            let [r, g, b] = pixel.0;
-
-            // Normalize to [0, 1] and then to standardized range for model
-            array[[0, 0, y as usize, x as usize]] = (r as f32 / 255.0 - 0.485) / 0.229;
-            array[[0, 1, y as usize, x as usize]] = (g as f32 / 255.0 - 0.456) / 0.224;
-            array[[0, 2, y as usize, x as usize]] = (b as f32 / 255.0 - 0.406) / 0.225;
+            for c in 0..3 {
+                array[[0, c, y as usize, x as usize]] =
+                    (pixel.0[c] as f32 / 255.0 - profile.mean[c]) / profile.std[c];
+            }
         }
 
         array
     };
 
-    // Run model inference
-    let mask_output: Array4<f32> = manager.run_inference(&preprocessed)?;
-
-    // Postprocess the mask
-    let mask = {
-        use image::imageops::FilterType;
+    let prompts = prompt_tensors(prompt, scale, original_width, original_height)?;
+    let outputs = manager.run_prompted_inference(&preprocessed, &prompts)?;
 
-        // gamma for visualization
-        let gamma: f32 = 0.5;
-
-        // extract first batch/channel
-        let temp_axis = mask_output.index_axis(Axis(0), 0);
+    // Union of all decoded masks
+    let mut probability = GrayImage::new(original_width, original_height);
+    for output in &outputs {
+        let temp_axis = output.index_axis(Axis(0), 0);
         let mask_data = temp_axis.index_axis(Axis(0), 0);
-        let (model_height, model_width) = mask_data.dim();
+        let mut mask_gray = probability_mask(mask_data, profile.normalization);
 
-        // build LUT
-        let g = gamma.clamp(0.2, 5.0);
-        let mut lut = [(0u8, 0u8, 0u8); 256];
-        for i in 0..256 {
-            let t = (i as f32 / 255.0).powf(g);
-            lut[i] = colormap(t);
+        if mask_gray.dimensions() != (original_width, original_height) {
+            mask_gray = image::imageops::resize(
+                &mask_gray,
+                original_width,
+                original_height,
+                image::imageops::FilterType::Lanczos3,
+            );
         }
 
-        // fill heatmap image
-        let mut heat = RgbImage::new(model_width as u32, model_height as u32);
-        for (x, y, pixel) in heat.enumerate_pixels_mut() {
-            let v = mask_data[[y as usize, x as usize]];
-            let s = 1.0 / (1.0 + (-v).exp());
-            let idx = (s * 255.0).round() as usize;
-            let (r, g, b) = lut[idx.min(255)];
-            *pixel = image::Rgb([r, g, b]);
+        for (dst, src) in probability.pixels_mut().zip(mask_gray.pixels()) {
+            dst.0[0] = dst.0[0].max(src.0[0]);
         }
+    }
 
-        image::imageops::resize(&heat, original_width, original_height, FilterType::Lanczos3)
+    apply_mask(image, probability, options)
+}
+
+/// Decoder inputs for a prompt, coordinates scaled by `scale` into encoder space.
+///
+/// Every box is decoded with all points; without boxes a padding point is appended.
+fn prompt_tensors(
+    prompt: &Prompt,
+    scale: f32,
+    width: u32,
+    height: u32,
+) -> Result<Vec<PromptTensors>, RembgError> {
+    let mut prompts = Vec::new();
+    let boxes: Vec<Option<_>> = if prompt.boxes.is_empty() {
+        vec![None]
+    } else {
+        prompt.boxes.iter().map(Some).collect()
     };
 
-    // Apply mask to original image
-    let result_image = {
-        // Convert input image to RGBA
-        let rgba_img = image.to_rgba8();
-        let (width, height) = rgba_img.dimensions();
+    for rect in boxes {
+        let mut coords = Vec::new();
+        let mut labels = Vec::new();
 
-        if mask_output.ndim() != 4 {
-            return Err(RembgError::PreprocessingError(format!(
-                "Unexpected mask shape: {:?}",
-                mask_output.shape()
-            )));
+        for point in &prompt.points {
+            coords.extend_from_slice(&[point.x * scale, point.y * scale]);
+            labels.push(match point.label {
+                PointLabel::Foreground => 1.0,
+                PointLabel::Background => 0.0,
+            });
         }
 
-        let temp_axis = mask_output.index_axis(Axis(0), 0);
-        let mask_data = temp_axis.index_axis(Axis(0), 0);
-        let (model_h, model_w) = mask_data.dim();
+        match rect {
+            Some(rect) => {
+                coords.extend_from_slice(&[rect.x as f32 * scale, rect.y as f32 * scale]);
+                coords.extend_from_slice(&[
+                    rect.right() as f32 * scale,
+                    rect.bottom() as f32 * scale,
+                ]);
+                labels.extend_from_slice(&[2.0, 3.0]);
+            }
+            None => {
+                coords.extend_from_slice(&[0.0, 0.0]);
+                labels.push(-1.0);
+            }
+        }
 
-        let need_resize = (model_w as u32 != width) || (model_h as u32 != height);
-        let mut mask_gray: ImageBuffer<Luma<u8>, Vec<u8>> =
-            ImageBuffer::new(model_w as u32, model_h as u32);
+        let count = labels.len();
+        prompts.push(PromptTensors {
+            point_coords: Array::from_shape_vec(IxDyn(&[1, count, 2]), coords)?,
+            point_labels: Array::from_shape_vec(IxDyn(&[1, count]), labels)?,
+            orig_im_size: [height as f32, width as f32],
+        });
+    }
 
-        for (x, y, pixel) in mask_gray.enumerate_pixels_mut() {
-            let v = mask_data[[y as usize, x as usize]];
-            let s = 1.0 / (1.0 + (-v).exp());
-            pixel.0[0] = (s * 255.0).clamp(0.0, 255.0) as u8;
-        }
+    Ok(prompts)
+}
 
-        let mask_resized = if need_resize {
-            image::imageops::resize(
-                &mask_gray,
-                width,
-                height,
-                image::imageops::FilterType::Lanczos3,
-            )
-        } else {
-            mask_gray
+/// Where the keyed alpha ramp crosses half coverage
+const SOFT_KEY_THRESHOLD: u8 = 128;
+
+/// Remove a uniform key color (green screen, flat art) without a model
+///
+/// The keyed mask goes through the same post-processing as [`rembg`]. With the default
+/// [`AlphaCurve::Linear`] the softness ramp is used as alpha directly, so edges stay soft.
+/// `threshold` and `auto_threshold` do not apply then: the reported threshold is 128,
+/// the middle of the ramp, and confidence and trimap are split there.
+/// `binary` or any other curve is applied as usual.
+pub fn rembg_chroma_key(
+    image: DynamicImage,
+    key: &ChromaKey,
+    options: &RemovalOptions,
+) -> Result<RemovalResult, RembgError> {
+    let image = matte_input(image, &options.input_alpha);
+    let probability = chroma_key_mask(&image.to_rgb8(), key);
+
+    // The keyed mask already is the alpha ramp, the default curve would cut it at the threshold
+    if !options.binary && options.alpha_curve == AlphaCurve::Linear {
+        let options = RemovalOptions {
+            alpha_curve: AlphaCurve::Levels {
+                low: 0,
+                high: 255,
+                gamma: 1.0,
+            },
+            threshold: SOFT_KEY_THRESHOLD,
+            auto_threshold: false,
+            ..options.clone()
         };
+        return apply_mask(image, probability, &options);
+    }
 
-        let mut result = RgbaImage::new(width, height);
-        let thr_u8 = options.threshold;
-        let thr_f = thr_u8 as f32;
+    apply_mask(image, probability, options)
+}
 
-        let smooth_scale = if thr_u8 < 255 {
-            Some(255.0 / (255.0 - thr_f))
-        } else {
-            None
+/// Remove a flat background connected to the image edges without a model
+///
+/// Gives pixel-exact masks for sprites and keeps enclosed areas of the background color.
+pub fn rembg_flood_fill(
+    image: DynamicImage,
+    fill: &FloodFill,
+    options: &RemovalOptions,
+) -> Result<RemovalResult, RembgError> {
+    let image = matte_input(image, &options.input_alpha);
+    let probability = flood_fill_mask(&image.to_rgb8(), fill);
+    apply_mask(image, probability, options)
+}
+
+/// Build the cutout from a probability mask (0 = background, 255 = foreground)
+///
+/// This is the post-processing shared by every segmentation path: the mask must have
+/// the same size as the image.
+pub fn apply_mask(
+    image: DynamicImage,
+    mut probability: GrayImage,
+    options: &RemovalOptions,
+) -> Result<RemovalResult, RembgError> {
+    let (width, height) = image.dimensions();
+
+    if probability.dimensions() != (width, height) {
+        return Err(RembgError::InvalidInput(format!(
+            "Mask size {:?} does not match image size {:?}",
+            probability.dimensions(),
+            (width, height)
+        )));
+    }
+
+    // Snap the model outline to the exact edge of a uniform background
+    let color_refined = match &options.color_refine {
+        Some(refine) => refine_with_background_color(&mut probability, &image.to_rgb8(), refine),
+        None => false,
+    };
+
+    apply_hints(&mut probability, &options.hints)?;
+
+    if let Some(roi) = options.roi {
+        for (x, y, pixel) in probability.enumerate_pixels_mut() {
+            if !roi.contains(x, y) {
+                pixel.0[0] = 0;
+            }
+        }
+    }
+
+    let mask = heatmap(&probability);
+
+    let threshold = if options.auto_threshold {
+        // The forced background outside the region of interest would dominate the histogram
+        let region = options.roi.and_then(|roi| roi.clamp_to(width, height));
+        let selected = match region {
+            Some(r) => select_threshold(
+                &image::imageops::crop_imm(&probability, r.x, r.y, r.width, r.height).to_image(),
+            ),
+            None => select_threshold(&probability),
         };
+        selected.unwrap_or(options.threshold)
+    } else {
+        options.threshold
+    };
+
+    // Metadata describes the cutout, which keeps the scene when inverted
+    let confidence = if options.invert {
+        inverted_mask_confidence(&probability, threshold)
+    } else {
+        mask_confidence(&probability, threshold)
+    };
+    let uncertainty = uncertainty_map(&probability);
+
+    let mut trimap = options
+        .trimap_band
+        .map(|band| trimap(&probability, threshold, band));
+
+    // Map probability to alpha
+    let lut = if options.binary {
+        let mut lut = [0u8; 256];
+        for (i, entry) in lut.iter_mut().enumerate() {
+            *entry = if i >= threshold as usize { 255 } else { 0 };
+        }
+        lut
+    } else {
+        options.alpha_curve.lut(threshold)?
+    };
+
+    let mut alpha = GrayImage::from_fn(width, height, |x, y| {
+        Luma([lut[probability.get_pixel(x, y).0[0] as usize]])
+    });
+
+    if !options.morphology.is_empty() {
+        alpha = apply_morphology(&alpha, &options.morphology);
+    }
+
+    if options.min_island_area > 0 || options.keep_largest_island {
+        remove_islands(
+            &mut alpha,
+            options.min_island_area,
+            options.keep_largest_island,
+        );
+    }
+
+    if options.max_hole_area > 0 {
+        fill_holes(&mut alpha, options.max_hole_area);
+    }
 
+    if options.binary && options.contour_smoothing > 0.0 {
+        alpha = smooth_contour(&alpha, options.contour_smoothing);
+    }
+
+    // Keep the scene and cut the subject out instead
+    if options.invert {
+        for pixel in alpha.pixels_mut() {
+            pixel.0[0] = 255 - pixel.0[0];
+        }
+
+        // The trimap describes the cutout, so it flips too
+        if let Some(trimap) = trimap.as_mut() {
+            invert_trimap(trimap);
+        }
+    }
+
+    // Transparent input pixels stay transparent
+    if options.input_alpha == InputAlpha::Multiply {
+        multiply_input_alpha(&mut alpha, &image);
+    }
+
+    // Apply mask to original image
+    let masked = {
+        // Convert input image to RGBA
+        let rgba_img = image.to_rgba8();
+
+        let mut result = RgbaImage::new(width, height);
         for (x, y, src) in rgba_img.enumerate_pixels() {
-            let mask_value = mask_resized.get_pixel(x, y).0[0];
+            let a = alpha.get_pixel(x, y).0[0];
+            result.put_pixel(x, y, Rgba([src.0[0], src.0[1], src.0[2], a]));
+        }
+        result
+    };
 
-            let alpha: u8 = if options.binary {
-                if mask_value >= thr_u8 { 255 } else { 0 }
-            } else {
-                match smooth_scale {
-                    Some(scale) => {
-                        let mv = mask_value as f32;
-                        ((mv - thr_f) * scale * 255.0).clamp(0.0, 255.0).round() as u8
-                    }
-                    None => {
-                        if mask_value == 255 {
-                            255
-                        } else {
-                            0
-                        }
-                    }
-                }
-            };
+    let result_image = {
+        let mut result = masked.clone();
+
+        if options.defringe > 0 {
+            defringe(&mut result, options.defringe);
+        }
 
-            result.put_pixel(x, y, Rgba([src.0[0], src.0[1], src.0[2], alpha]));
+        if let Some(settings) = &options.despill {
+            despill(&mut result, settings);
         }
 
         if options.sticker {
@@ -157,12 +430,129 @@ pub fn rembg(
         result
     };
 
+    let composite = options
+        .background
+        .as_ref()
+        .map(|background| replace_background(&result_image, &masked, background));
+
+    let geometry = subject_geometry(&result_image);
+    let subjects = if options.split_subjects {
+        split_subjects(&result_image)
+    } else {
+        Vec::new()
+    };
+
     Ok(RemovalResult {
         image: result_image,
         mask,
+        threshold,
+        trimap,
+        confidence,
+        uncertainty,
+        geometry,
+        subjects,
+        composite,
+        color_refined,
     })
 }
 
+/// Pick a cut point from the probability histogram with Otsu's method.
+/// Returns `None` for a flat mask where no split exists.
+fn select_threshold(probability: &GrayImage) -> Option<u8> {
+    let mut histogram = [0u64; 256];
+    for pixel in probability.pixels() {
+        histogram[pixel.0[0] as usize] += 1;
+    }
+
+    if histogram.iter().filter(|&&count| count > 0).count() < 2 {
+        return None;
+    }
+
+    // Pixels strictly above the Otsu level form the upper class
+    let level = otsu_level(probability);
+    Some(level.saturating_add(1))
+}
+
+/// Resize to the model input and normalize with the profile, shape [1, 3, size, size]
+fn model_input(rgb_img: &RgbImage, profile: &ModelProfile) -> Array4<f32> {
+    let size = profile.input_size;
+    let resized =
+        image::imageops::resize(rgb_img, size, size, image::imageops::FilterType::Lanczos3);
+
+    let scale = match profile.scaling {
+        InputScaling::Fixed => 255.0,
+        InputScaling::ImageMax => {
+            let max = resized.as_raw().iter().copied().max().unwrap_or(0);
+            (max as f32).max(1e-6)
+        }
+    };
+
+    let mut array = Array4::<f32>::zeros((1, 3, size as usize, size as usize));
+    for (x, y, pixel) in resized.enumerate_pixels() {
+        // Scale to [0, 1] and then to standardized range for model
+        for c in 0..3 {
+            array[[0, c, y as usize, x as usize]] =
+                (pixel.0[c] as f32 / scale - profile.mean[c]) / profile.std[c];
+        }
+    }
+    array
+}
+
+/// Convert raw model output to an 8-bit probability mask
+fn probability_mask(mask_data: ArrayView2<f32>, normalization: OutputNormalization) -> GrayImage {
+    let (model_h, model_w) = mask_data.dim();
+    let mut mask_gray = GrayImage::new(model_w as u32, model_h as u32);
+
+    let (min, max) = mask_data
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v), hi.max(v))
+        });
+    let range = max - min;
+
+    for (x, y, pixel) in mask_gray.enumerate_pixels_mut() {
+        let v = mask_data[[y as usize, x as usize]];
+        let s = match normalization {
+            OutputNormalization::Sigmoid => 1.0 / (1.0 + (-v).exp()),
+            OutputNormalization::MinMax => {
+                if range > 0.0 {
+                    (v - min) / range
+                } else {
+                    0.0
+                }
+            }
+            OutputNormalization::Identity => v,
+        };
+        pixel.0[0] = (s * 255.0).clamp(0.0, 255.0) as u8;
+    }
+
+    mask_gray
+}
+
+/// Colorize a probability mask for visualization
+fn heatmap(probability: &GrayImage) -> RgbImage {
+    // gamma for visualization
+    let gamma: f32 = 0.5;
+
+    // build LUT
+    let g = gamma.clamp(0.2, 5.0);
+    let mut lut = [(0u8, 0u8, 0u8); 256];
+    for (i, entry) in lut.iter_mut().enumerate() {
+        let t = (i as f32 / 255.0).powf(g);
+        *entry = colormap(t);
+    }
+
+    // fill heatmap image
+    let (width, height) = probability.dimensions();
+    let mut heat = RgbImage::new(width, height);
+    for (dst, src) in heat.pixels_mut().zip(probability.pixels()) {
+        let (r, g, b) = lut[src.0[0] as usize];
+        *dst = image::Rgb([r, g, b]);
+    }
+
+    heat
+}
+
 // --- Inlined preprocessor/processor helpers ---
 
 #[inline]
@@ -199,3 +589,179 @@ fn colormap(t: f32) -> (u8, u8, u8) {
     }
     STOPS.last().unwrap().1
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+    use ndarray::Array2;
+
+    fn values(mask: &GrayImage) -> Vec<u8> {
+        mask.pixels().map(|p| p.0[0]).collect()
+    }
+
+    #[test]
+    fn min_max_matches_python_rembg() {
+        let logits = Array2::from_shape_vec((2, 3), vec![-4.0, -1.5, 0.0, 0.25, 2.0, 6.0]).unwrap();
+
+        // python rembg: ((pred - mi) / (ma - mi) * 255).astype("uint8")
+        let (min, max) = (-4.0f32, 6.0f32);
+        let expected: Vec<u8> = logits
+            .iter()
+            .map(|&v| ((v - min) / (max - min) * 255.0) as u8)
+            .collect();
+
+        let mask = probability_mask(logits.view(), OutputNormalization::MinMax);
+        assert_eq!(mask.dimensions(), (3, 2));
+        assert_eq!(values(&mask), expected);
+        assert_eq!(values(&mask), vec![0, 63, 102, 108, 153, 255]);
+    }
+
+    /// Reference values from python rembg's u2net pre- and post-processing
+    fn u2net_reference(key: &str) -> Vec<f32> {
+        include_str!("../tests/fixtures/u2net_reference.txt")
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(' '))
+            .unwrap()
+            .split_whitespace()
+            .map(|v| v.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn u2net_profile_matches_python_rembg_reference() {
+        let size = u2net_reference("size")[0] as usize;
+        let pixels: Vec<u8> = u2net_reference("image").iter().map(|&v| v as u8).collect();
+        let image = RgbImage::from_raw(size as u32, size as u32, pixels).unwrap();
+        let profile = ModelProfile {
+            input_size: size as u32,
+            ..ModelProfile::u2net()
+        };
+
+        let input = model_input(&image, &profile);
+        for (actual, expected) in input.iter().zip(u2net_reference("input")) {
+            assert!(
+                (actual - expected).abs() < 1e-5,
+                "{} vs {}",
+                actual,
+                expected
+            );
+        }
+
+        let output = Array2::from_shape_vec((size, size), u2net_reference("output")).unwrap();
+        let mask = probability_mask(output.view(), profile.normalization);
+        let expected: Vec<u8> = u2net_reference("mask").iter().map(|&v| v as u8).collect();
+        assert_eq!(values(&mask), expected);
+    }
+
+    #[test]
+    fn min_max_of_constant_output_is_background() {
+        let logits = Array2::from_elem((2, 2), 3.5f32);
+        let mask = probability_mask(logits.view(), OutputNormalization::MinMax);
+        assert_eq!(values(&mask), vec![0; 4]);
+    }
+
+    #[test]
+    fn sigmoid_maps_logits() {
+        let logits = Array2::from_shape_vec((1, 3), vec![-20.0, 0.0, 20.0]).unwrap();
+        let mask = probability_mask(logits.view(), OutputNormalization::Sigmoid);
+        assert_eq!(values(&mask), vec![0, 127, 255]);
+    }
+
+    #[test]
+    fn identity_clamps_probabilities() {
+        let probs = Array2::from_shape_vec((1, 4), vec![-0.5, 0.0, 0.5, 1.5]).unwrap();
+        let mask = probability_mask(probs.view(), OutputNormalization::Identity);
+        assert_eq!(values(&mask), vec![0, 0, 127, 255]);
+    }
+
+    #[test]
+    fn auto_threshold_ignores_pixels_outside_the_roi() {
+        // Small bimodal region of interest in a big empty image
+        let mut probability = GrayImage::new(100, 100);
+        for y in 0..10 {
+            for x in 0..10 {
+                let value = if x < 5 { 100 } else { 220 };
+                probability.put_pixel(x, y, Luma([value]));
+            }
+        }
+        let image = DynamicImage::ImageRgb8(RgbImage::new(100, 100));
+        let options = crate::options::RemovalOptionsBuilder::default()
+            .auto_threshold(true)
+            .roi(Some(Rect::new(0, 0, 10, 10)))
+            .build()
+            .unwrap();
+
+        let result = apply_mask(image, probability, &options).unwrap();
+        assert!(result.threshold() > 100 && result.threshold() <= 220);
+    }
+
+    #[test]
+    fn soft_chroma_key_reports_the_threshold_it_uses() {
+        let image = RgbImage::from_fn(4, 1, |x, _| {
+            if x < 2 {
+                Rgb([0, 255, 0])
+            } else {
+                Rgb([200, 50, 50])
+            }
+        });
+        let options = crate::options::RemovalOptionsBuilder::default()
+            .threshold(200)
+            .auto_threshold(true)
+            .build()
+            .unwrap();
+
+        let result = rembg_chroma_key(
+            DynamicImage::ImageRgb8(image),
+            &ChromaKey::new([0, 255, 0]),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(result.threshold(), SOFT_KEY_THRESHOLD);
+        assert_eq!(result.image().get_pixel(0, 0)[3], 0);
+        assert_eq!(result.image().get_pixel(3, 0)[3], 255);
+    }
+
+    #[test]
+    fn prompt_points_are_scaled_and_padded() {
+        let prompt = Prompt::new().foreground(100.0, 50.0).background(10.0, 20.0);
+        let tensors = prompt_tensors(&prompt, 0.5, 400, 300).unwrap();
+
+        assert_eq!(tensors.len(), 1);
+        let t = &tensors[0];
+        assert_eq!(t.point_coords.shape(), &[1, 3, 2]);
+        assert_eq!(
+            t.point_coords.iter().copied().collect::<Vec<_>>(),
+            vec![50.0, 25.0, 5.0, 10.0, 0.0, 0.0]
+        );
+        assert_eq!(
+            t.point_labels.iter().copied().collect::<Vec<_>>(),
+            vec![1.0, 0.0, -1.0]
+        );
+        assert_eq!(t.orig_im_size, [300.0, 400.0]);
+    }
+
+    #[test]
+    fn prompt_boxes_use_corner_labels() {
+        let prompt = Prompt::new()
+            .foreground(8.0, 8.0)
+            .with_box(Rect::new(10, 20, 30, 40))
+            .with_box(Rect::new(0, 0, 4, 4));
+        let tensors = prompt_tensors(&prompt, 2.0, 100, 100).unwrap();
+
+        // One decoder run per box, each with all points and no padding point
+        assert_eq!(tensors.len(), 2);
+        assert_eq!(
+            tensors[0].point_coords.iter().copied().collect::<Vec<_>>(),
+            vec![16.0, 16.0, 20.0, 40.0, 80.0, 120.0]
+        );
+        assert_eq!(
+            tensors[0].point_labels.iter().copied().collect::<Vec<_>>(),
+            vec![1.0, 2.0, 3.0]
+        );
+        assert_eq!(
+            tensors[1].point_coords.iter().copied().collect::<Vec<_>>(),
+            vec![16.0, 16.0, 0.0, 0.0, 8.0, 8.0]
+        );
+    }
+}
@@ -1,8 +1,28 @@
-use image::{RgbImage, RgbaImage};
+use crate::confidence::MaskConfidence;
+use crate::geometry::SubjectGeometry;
+use crate::subjects::Subject;
+use image::{GrayImage, RgbImage, RgbaImage};
 
 pub struct RemovalResult {
     pub image: RgbaImage,
     pub mask: RgbImage,
+    /// Threshold that was applied, chosen automatically when `auto_threshold` is set
+    pub threshold: u8,
+    /// Foreground (255), background (0) and unknown (128) regions, when requested
+    pub trimap: Option<GrayImage>,
+    /// Scalar quality estimate of the mask with empty/full flags
+    pub confidence: MaskConfidence,
+    /// Per-pixel uncertainty, 255 where the model is undecided
+    pub uncertainty: GrayImage,
+    /// Bounding box, centroid, area and coverage of the cutout
+    pub geometry: SubjectGeometry,
+    /// Separate cutouts per connected object, largest first, when requested
+    pub subjects: Vec<Subject>,
+    /// Opaque cutout over the replacement background, when one is set
+    pub composite: Option<RgbImage>,
+    /// Whether `color_refine` changed the mask; false when it is off or the border
+    /// was not a uniform color
+    pub color_refined: bool,
 }
 
 impl RemovalResult {
@@ -14,6 +34,38 @@ impl RemovalResult {
         &self.mask
     }
 
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn trimap(&self) -> Option<&GrayImage> {
+        self.trimap.as_ref()
+    }
+
+    pub fn confidence(&self) -> &MaskConfidence {
+        &self.confidence
+    }
+
+    pub fn uncertainty(&self) -> &GrayImage {
+        &self.uncertainty
+    }
+
+    pub fn geometry(&self) -> &SubjectGeometry {
+        &self.geometry
+    }
+
+    pub fn subjects(&self) -> &[Subject] {
+        &self.subjects
+    }
+
+    pub fn composite(&self) -> Option<&RgbImage> {
+        self.composite.as_ref()
+    }
+
+    pub fn color_refined(&self) -> bool {
+        self.color_refined
+    }
+
     pub fn into_parts(self) -> (RgbaImage, RgbImage) {
         (self.image, self.mask)
     }
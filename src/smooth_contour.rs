@@ -0,0 +1,127 @@
+use image::{GrayImage, ImageBuffer, Luma};
+use imageproc::filter::gaussian_blur_f32;
+
+/// Smooths the outline of a binary alpha mask and anti-aliases it.
+///
+/// The mask is blurred with a Gaussian of `sigma` pixels and the 0.5 level set of the
+/// blurred field becomes the new outline. Edge pixels get their coverage from 4x4
+/// supersampling, pixels away from the edge stay fully opaque or transparent.
+pub fn smooth_contour(alpha: &GrayImage, sigma: f32) -> GrayImage {
+    let (w, h) = alpha.dimensions();
+    if !sigma.is_finite() || sigma <= 0.0 || w == 0 || h == 0 {
+        return alpha.clone();
+    }
+
+    let field: ImageBuffer<Luma<f32>, Vec<f32>> =
+        ImageBuffer::from_fn(w, h, |x, y| Luma([alpha.get_pixel(x, y)[0] as f32 / 255.0]));
+    let field = gaussian_blur_f32(&field, sigma);
+
+    // bilinear sample with pixel centers at integer coordinates
+    let sample = |fx: f32, fy: f32| -> f32 {
+        let fx = fx.clamp(0.0, (w - 1) as f32);
+        let fy = fy.clamp(0.0, (h - 1) as f32);
+        let x0 = fx.floor() as u32;
+        let y0 = fy.floor() as u32;
+        let x1 = (x0 + 1).min(w - 1);
+        let y1 = (y0 + 1).min(h - 1);
+        let tx = fx - x0 as f32;
+        let ty = fy - y0 as f32;
+        let top = field.get_pixel(x0, y0)[0] * (1.0 - tx) + field.get_pixel(x1, y0)[0] * tx;
+        let bottom = field.get_pixel(x0, y1)[0] * (1.0 - tx) + field.get_pixel(x1, y1)[0] * tx;
+        top * (1.0 - ty) + bottom * ty
+    };
+
+    const SUB: u32 = 4;
+
+    let mut out = GrayImage::new(w, h);
+    for y in 0..h {
+        for x in 0..w {
+            // Fast path: the whole 3x3 neighbourhood is on one side of the level set
+            let mut above = 0;
+            let mut total = 0;
+            for ny in y.saturating_sub(1)..=(y + 1).min(h - 1) {
+                for nx in x.saturating_sub(1)..=(x + 1).min(w - 1) {
+                    total += 1;
+                    if field.get_pixel(nx, ny)[0] >= 0.5 {
+                        above += 1;
+                    }
+                }
+            }
+            if above == 0 || above == total {
+                let value = if above == 0 { 0 } else { 255 };
+                out.put_pixel(x, y, Luma([value]));
+                continue;
+            }
+
+            // Edge pixel: coverage of the smoothed shape
+            let mut covered = 0;
+            for sy in 0..SUB {
+                for sx in 0..SUB {
+                    let fx = x as f32 + (sx as f32 + 0.5) / SUB as f32 - 0.5;
+                    let fy = y as f32 + (sy as f32 + 0.5) / SUB as f32 - 0.5;
+                    if sample(fx, fy) >= 0.5 {
+                        covered += 1;
+                    }
+                }
+            }
+            let value = (covered * 255 + (SUB * SUB) / 2) / (SUB * SUB);
+            out.put_pixel(x, y, Luma([value as u8]));
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(size: u32, from: u32, to: u32) -> GrayImage {
+        GrayImage::from_fn(size, size, |x, y| {
+            let inside = (from..to).contains(&x) && (from..to).contains(&y);
+            Luma([if inside { 255 } else { 0 }])
+        })
+    }
+
+    #[test]
+    fn zero_sigma_returns_the_mask_unchanged() {
+        let alpha = square(16, 4, 12);
+        assert_eq!(smooth_contour(&alpha, 0.0), alpha);
+    }
+
+    #[test]
+    fn invalid_sigma_returns_the_mask_unchanged() {
+        let alpha = square(16, 4, 12);
+        for sigma in [f32::NAN, f32::INFINITY, -1.0] {
+            assert_eq!(smooth_contour(&alpha, sigma), alpha);
+        }
+    }
+
+    #[test]
+    fn keeps_interior_and_background_and_rounds_corners() {
+        let smoothed = smooth_contour(&square(32, 8, 24), 2.0);
+
+        assert_eq!(smoothed.get_pixel(16, 16)[0], 255);
+        assert_eq!(smoothed.get_pixel(1, 1)[0], 0);
+        // Straight edges stay put, the corner is cut
+        assert!(smoothed.get_pixel(8, 16)[0] > 128);
+        assert!(smoothed.get_pixel(8, 8)[0] < 128);
+    }
+
+    #[test]
+    fn anti_aliases_a_staircase() {
+        let stairs = GrayImage::from_fn(24, 24, |x, y| Luma([if x / 2 > y / 2 { 255 } else { 0 }]));
+        let smoothed = smooth_contour(&stairs, 1.5);
+
+        let partial = smoothed.pixels().filter(|p| p[0] > 0 && p[0] < 255).count();
+        assert!(partial > 0);
+    }
+
+    #[test]
+    fn removes_single_pixel_specks() {
+        let mut alpha = GrayImage::new(9, 9);
+        alpha.put_pixel(4, 4, Luma([255]));
+        let smoothed = smooth_contour(&alpha, 2.0);
+        assert!(smoothed.pixels().all(|p| p[0] == 0));
+    }
+}
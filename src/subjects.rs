@@ -0,0 +1,60 @@
+use crate::islands::component_areas;
+use crate::rect::Rect;
+use image::{GrayImage, Luma, RgbaImage};
+use imageproc::region_labelling::{Connectivity, connected_components};
+
+/// One separate object of the cutout
+#[derive(Debug, Clone)]
+pub struct Subject {
+    /// Position of the cutout in the full image
+    pub bbox: Rect,
+    /// Cropped RGBA image containing only this subject
+    pub image: RgbaImage,
+}
+
+/// Split a cutout into its connected components (alpha > 0, 8-connected),
+/// largest first.
+pub fn split_subjects(image: &RgbaImage) -> Vec<Subject> {
+    let (w, h) = image.dimensions();
+    let binary = GrayImage::from_fn(w, h, |x, y| {
+        Luma([if image.get_pixel(x, y)[3] > 0 { 255 } else { 0 }])
+    });
+    let labels = connected_components(&binary, Connectivity::Eight, Luma([0u8]));
+    let areas = component_areas(&labels);
+
+    // Bounds per label as (min_x, min_y, max_x, max_y)
+    let mut bounds = vec![(u32::MAX, u32::MAX, 0u32, 0u32); areas.len()];
+    for (x, y, label) in labels.enumerate_pixels() {
+        let label = label[0] as usize;
+        if label == 0 {
+            continue;
+        }
+        let b = &mut bounds[label];
+        b.0 = b.0.min(x);
+        b.1 = b.1.min(y);
+        b.2 = b.2.max(x);
+        b.3 = b.3.max(y);
+    }
+
+    let mut order: Vec<usize> = (1..areas.len()).filter(|&l| areas[l] > 0).collect();
+    order.sort_by(|a, b| areas[*b].cmp(&areas[*a]));
+
+    order
+        .into_iter()
+        .map(|label| {
+            let (x0, y0, x1, y1) = bounds[label];
+            let bbox = Rect::new(x0, y0, x1 - x0 + 1, y1 - y0 + 1);
+            let mut cutout = RgbaImage::new(bbox.width, bbox.height);
+            for (x, y, pixel) in cutout.enumerate_pixels_mut() {
+                let (sx, sy) = (x + bbox.x, y + bbox.y);
+                if labels.get_pixel(sx, sy)[0] as usize == label {
+                    *pixel = *image.get_pixel(sx, sy);
+                }
+            }
+            Subject {
+                bbox,
+                image: cutout,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,50 @@
+use image::{GrayImage, Luma};
+use imageproc::distance_transform::Norm;
+use imageproc::morphology::{dilate, erode};
+
+/// Trimap value of definite background
+pub const TRIMAP_BACKGROUND: u8 = 0;
+/// Trimap value of the unknown band
+pub const TRIMAP_UNKNOWN: u8 = 128;
+/// Trimap value of definite foreground
+pub const TRIMAP_FOREGROUND: u8 = 255;
+
+/// Build a trimap from a probability mask.
+///
+/// Pixels at or above `threshold` are foreground. Everything within `band` pixels of the
+/// foreground outline, on either side, is marked unknown.
+pub fn trimap(probability: &GrayImage, threshold: u8, band: u8) -> GrayImage {
+    let (w, h) = probability.dimensions();
+    let foreground = GrayImage::from_fn(w, h, |x, y| {
+        Luma([if probability.get_pixel(x, y)[0] >= threshold {
+            255
+        } else {
+            0
+        }])
+    });
+
+    let inner = erode(&foreground, Norm::L2, band);
+    let outer = dilate(&foreground, Norm::L2, band);
+
+    GrayImage::from_fn(w, h, |x, y| {
+        let value = if inner.get_pixel(x, y)[0] != 0 {
+            TRIMAP_FOREGROUND
+        } else if outer.get_pixel(x, y)[0] == 0 {
+            TRIMAP_BACKGROUND
+        } else {
+            TRIMAP_UNKNOWN
+        };
+        Luma([value])
+    })
+}
+
+/// Swap the foreground and background regions, the unknown band stays
+pub fn invert_trimap(trimap: &mut GrayImage) {
+    for pixel in trimap.pixels_mut() {
+        pixel.0[0] = match pixel.0[0] {
+            TRIMAP_FOREGROUND => TRIMAP_BACKGROUND,
+            TRIMAP_BACKGROUND => TRIMAP_FOREGROUND,
+            other => other,
+        };
+    }
+}
@@ -0,0 +1,221 @@
+use image::{GrayImage, Luma, RgbImage};
+
+/// How the low resolution model mask is scaled to the image size
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MaskUpsampling {
+    /// Plain Lanczos3 resampling
+    #[default]
+    Lanczos3,
+    /// Joint bilateral upsampling guided by the full resolution image,
+    /// so mask edges follow image edges
+    JointBilateral {
+        /// Spatial sigma in low resolution mask pixels
+        spatial_sigma: f32,
+        /// Color difference sigma in 0–255 RGB units
+        range_sigma: f32,
+    },
+}
+
+impl MaskUpsampling {
+    pub fn joint_bilateral() -> Self {
+        MaskUpsampling::JointBilateral {
+            spatial_sigma: 1.0,
+            range_sigma: 25.0,
+        }
+    }
+}
+
+/// Upsample `mask` to the size of `guide` with a joint bilateral filter.
+///
+/// Every output pixel is a weighted average of nearby mask samples, weighted by
+/// distance in the mask grid and by how similar the guide color at the sample is
+/// to the guide color of the output pixel.
+pub fn joint_bilateral_upsample(
+    mask: &GrayImage,
+    guide: &RgbImage,
+    spatial_sigma: f32,
+    range_sigma: f32,
+) -> GrayImage {
+    let (lw, lh) = mask.dimensions();
+    let (w, h) = guide.dimensions();
+    if lw == 0 || lh == 0 || w == 0 || h == 0 {
+        return GrayImage::new(w, h);
+    }
+
+    let spatial_sigma = spatial_sigma.max(0.1);
+    let range_sigma = range_sigma.max(0.1);
+
+    // Guide colors at the mask sample positions
+    let guide_low = image::imageops::resize(guide, lw, lh, image::imageops::FilterType::Triangle);
+
+    let sx = lw as f32 / w as f32;
+    let sy = lh as f32 / h as f32;
+    let radius = (2.0 * spatial_sigma).ceil() as i64;
+    let spatial_k = -0.5 / (spatial_sigma * spatial_sigma);
+    let range_k = -0.5 / (range_sigma * range_sigma);
+
+    let mut out = GrayImage::new(w, h);
+    for (x, y, px) in out.enumerate_pixels_mut() {
+        let u = (x as f32 + 0.5) * sx - 0.5;
+        let v = (y as f32 + 0.5) * sy - 0.5;
+        let cu = u.round() as i64;
+        let cv = v.round() as i64;
+        let center = guide.get_pixel(x, y).0;
+
+        let mut sum = 0.0f32;
+        let mut weight_sum = 0.0f32;
+        for qy in (cv - radius).max(0)..=(cv + radius).min(lh as i64 - 1) {
+            for qx in (cu - radius).max(0)..=(cu + radius).min(lw as i64 - 1) {
+                let du = qx as f32 - u;
+                let dv = qy as f32 - v;
+                let color = guide_low.get_pixel(qx as u32, qy as u32).0;
+                let dc: f32 = (0..3)
+                    .map(|c| {
+                        let d = color[c] as f32 - center[c] as f32;
+                        d * d
+                    })
+                    .sum();
+
+                let weight = ((du * du + dv * dv) * spatial_k + dc * range_k).exp();
+                sum += weight * mask.get_pixel(qx as u32, qy as u32)[0] as f32;
+                weight_sum += weight;
+            }
+        }
+
+        let value = if weight_sum > 0.0 {
+            sum / weight_sum
+        } else {
+            let qx = (u.round() as i64).clamp(0, lw as i64 - 1) as u32;
+            let qy = (v.round() as i64).clamp(0, lh as i64 - 1) as u32;
+            mask.get_pixel(qx, qy)[0] as f32
+        };
+        *px = Luma([value.round().clamp(0.0, 255.0) as u8]);
+    }
+
+    out
+}
+
+impl std::str::FromStr for MaskUpsampling {
+    type Err = String;
+
+    /// Parse `lanczos3` or `bilateral[:SPATIAL,RANGE]`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match (s, s.split_once(':')) {
+            ("lanczos3", None) => Ok(MaskUpsampling::Lanczos3),
+            ("bilateral", None) => Ok(MaskUpsampling::joint_bilateral()),
+            (_, Some(("bilateral", params))) => {
+                let values: Vec<f32> = params
+                    .split(',')
+                    .map(|v| v.trim().parse::<f32>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| format!("invalid bilateral sigmas '{}': {}", params, e))?;
+                if values.iter().any(|v| !(v.is_finite() && *v > 0.0)) {
+                    return Err(format!(
+                        "invalid bilateral sigmas '{}': expected positive values",
+                        params
+                    ));
+                }
+                match values.as_slice() {
+                    [spatial_sigma, range_sigma] => Ok(MaskUpsampling::JointBilateral {
+                        spatial_sigma: *spatial_sigma,
+                        range_sigma: *range_sigma,
+                    }),
+                    _ => Err(format!(
+                        "invalid bilateral sigmas '{}': expected SPATIAL,RANGE",
+                        params
+                    )),
+                }
+            }
+            _ => Err(format!(
+                "invalid upsampling '{}': expected lanczos3 or bilateral[:SPATIAL,RANGE]",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    /// Black left of `edge`, white from it
+    fn step_guide(w: u32, h: u32, edge: u32) -> RgbImage {
+        RgbImage::from_fn(w, h, |x, _| {
+            if x < edge {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb([255, 255, 255])
+            }
+        })
+    }
+
+    /// Pixels of the middle row that are neither clearly background nor foreground
+    fn blurred(mask: &GrayImage) -> usize {
+        let y = mask.height() / 2;
+        (0..mask.width())
+            .filter(|&x| (32..224).contains(&mask.get_pixel(x, y)[0]))
+            .count()
+    }
+
+    #[test]
+    fn constant_mask_stays_constant() {
+        let mask = GrayImage::from_pixel(8, 6, Luma([173]));
+        let guide = step_guide(32, 24, 13);
+        let out = joint_bilateral_upsample(&mask, &guide, 1.0, 25.0);
+
+        assert_eq!(out.dimensions(), (32, 24));
+        assert!(out.pixels().all(|p| p[0] == 173));
+    }
+
+    #[test]
+    fn guide_edge_sharpens_the_mask_edge() {
+        // Low resolution mask of the same step, soft where the edge falls between samples
+        let guide = step_guide(32, 8, 13);
+        let full = GrayImage::from_fn(32, 8, |x, _| Luma([if x < 13 { 0 } else { 255 }]));
+        let mask = image::imageops::resize(&full, 8, 2, image::imageops::FilterType::Triangle);
+
+        let bilateral = joint_bilateral_upsample(&mask, &guide, 1.0, 25.0);
+        let lanczos = image::imageops::resize(&mask, 32, 8, image::imageops::FilterType::Lanczos3);
+
+        assert!(
+            blurred(&bilateral) < blurred(&lanczos),
+            "bilateral {} vs lanczos {}",
+            blurred(&bilateral),
+            blurred(&lanczos)
+        );
+        assert!(bilateral.get_pixel(11, 4)[0] < 32);
+        assert!(bilateral.get_pixel(14, 4)[0] >= 224);
+    }
+
+    #[test]
+    fn parses_upsampling() {
+        assert_eq!("lanczos3".parse(), Ok(MaskUpsampling::Lanczos3));
+        assert_eq!("bilateral".parse(), Ok(MaskUpsampling::joint_bilateral()));
+        assert_eq!(
+            "bilateral:2, 10.5".parse(),
+            Ok(MaskUpsampling::JointBilateral {
+                spatial_sigma: 2.0,
+                range_sigma: 10.5,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_bad_upsampling() {
+        for s in [
+            "",
+            "nearest",
+            "lanczos3:1",
+            "bilateral:",
+            "bilateral:1",
+            "bilateral:1,2,3",
+            "bilateral:a,b",
+            "bilateral:0,10",
+            "bilateral:NaN,10",
+            "bilateral:1,-5",
+        ] {
+            assert!(s.parse::<MaskUpsampling>().is_err(), "{}", s);
+        }
+    }
+}
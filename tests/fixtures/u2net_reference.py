@@ -0,0 +1,63 @@
+"""Regenerate u2net_reference.txt.
+
+Follows python rembg's BaseSession.normalize and U2netSession.predict step by step
+(rembg 2.0, sessions/base.py and sessions/u2net.py) without numpy: the input is
+scaled by its maximum, normalized in float64 and cast to float32; the output is
+min-max stretched in float32 and truncated with astype("uint8"). The image already
+has the model input size, so the Lanczos resize is the identity.
+"""
+
+import struct
+
+MEAN = (0.485, 0.456, 0.406)
+STD = (0.229, 0.224, 0.225)
+SIZE = 3
+
+# RGB rows, brightest value 200 so max scaling differs from /255
+IMAGE = [
+    [(200, 10, 0), (120, 120, 120), (0, 0, 0)],
+    [(35, 180, 90), (64, 64, 200), (199, 150, 3)],
+    [(1, 2, 3), (100, 50, 25), (180, 190, 170)],
+]
+
+# Raw model output [1, 1, SIZE, SIZE]
+OUTPUT = [
+    [-3.75, -1.0, 0.5],
+    [2.25, 7.5, -0.125],
+    [4.0, 1.0, 6.25],
+]
+
+
+def f32(v):
+    return struct.unpack("f", struct.pack("f", v))[0]
+
+
+def model_input():
+    im_max = max(max(max(p) for p in row) for row in IMAGE)
+    scale = max(im_max, 1e-6)
+    return [
+        f32((row[x][c] / scale - MEAN[c]) / STD[c])
+        for c in range(3)
+        for row in IMAGE
+        for x in range(SIZE)
+    ]
+
+
+def mask():
+    pred = [f32(v) for row in OUTPUT for v in row]
+    ma, mi = max(pred), min(pred)
+    span = f32(ma - mi)
+    return [int(f32(f32(f32(v - mi) / span) * f32(255.0))) for v in pred]
+
+
+def main():
+    lines = ["# generated by u2net_reference.py", f"size {SIZE}"]
+    lines.append("image " + " ".join(str(c) for row in IMAGE for p in row for c in p))
+    lines.append("input " + " ".join(repr(v) for v in model_input()))
+    lines.append("output " + " ".join(repr(f32(v)) for row in OUTPUT for v in row))
+    lines.append("mask " + " ".join(str(v) for v in mask()))
+    print("\n".join(lines))
+
+
+if __name__ == "__main__":
+    main()